@@ -1,25 +1,84 @@
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 use crate::api::params;
+use crate::storage::strategy::Edit;
+use crate::storage::{Details, Word};
 use crate::UserWords;
 
-use hyper::header::HeaderValue;
+use hyper::header::{HeaderValue, AUTHORIZATION};
 use hyper::{Body, Error, Request, Response, StatusCode};
 use log::{error, warn};
 use serde::Serialize;
 use serde_json;
 
 pub fn list_words(
-    user_words: Arc<RwLock<UserWords>>,
+    user_words: Arc<UserWords>,
     req: &Request<Body>,
 ) -> Result<Response<Body>, Error> {
-    let mut resp = match params::user_id(&req) {
-        Ok(user_id) => {
-            let user_w = user_words.read().unwrap();
-            match user_w.list_words(user_id.user_id, None) {
+    let mut resp = match params::user_id(req) {
+        Ok(user_id) => match user_words.list_words(user_id.user_id, None) {
+            Ok(words) => json_response(&words),
+            Err(e) => {
+                error!("Can't get words list: {}", e);
+                internal_server_error_response()
+            }
+        },
+        Err(e) => {
+            warn!("Params parse error: {}", e);
+            unauthorized_response()
+        }
+    };
+    resp.headers_mut().insert(
+        "Content-Type",
+        HeaderValue::from_str("application/json; charset=utf-8").unwrap(),
+    );
+
+    Ok(resp)
+}
+
+// Moves words back from the trash and returns them
+pub fn restore_word(
+    user_words: Arc<UserWords>,
+    req: &Request<Body>,
+) -> Result<Response<Body>, Error> {
+    let mut resp = match params::restore_word(req) {
+        Ok(params) => match user_words.restore_word(params.user_id, params.word.as_deref()) {
+            Ok(words) => json_response(&words),
+            Err(e) => {
+                error!("Can't restore words: {}", e);
+                internal_server_error_response()
+            }
+        },
+        Err(e) => {
+            warn!("Params parse error: {}", e);
+            unauthorized_response()
+        }
+    };
+    resp.headers_mut().insert(
+        "Content-Type",
+        HeaderValue::from_str("application/json; charset=utf-8").unwrap(),
+    );
+
+    Ok(resp)
+}
+
+// Adds the user's translation of a word and returns entries of the word
+pub fn add_translation(
+    user_words: Arc<UserWords>,
+    req: &Request<Body>,
+) -> Result<Response<Body>, Error> {
+    let mut resp = match params::add_translation(req) {
+        Ok(params) => {
+            let tran = Word {
+                word: params.translation,
+                lang: params.lang,
+                pos: None,
+                manual: true,
+            };
+            match user_words.add_translation(params.user_id, &params.word, &tran) {
                 Ok(words) => json_response(&words),
                 Err(e) => {
-                    error!("Can't get words list: {}", e);
+                    error!("Can't add translation: {}", e);
                     internal_server_error_response()
                 }
             }
@@ -37,17 +96,77 @@ pub fn list_words(
     Ok(resp)
 }
 
-pub fn list_langs(
-    user_words: Arc<RwLock<UserWords>>,
+pub fn delete_translation(
+    user_words: Arc<UserWords>,
+    req: &Request<Body>,
+) -> Result<Response<Body>, Error> {
+    let mut resp = match params::delete_translation(req) {
+        Ok(params) => match user_words.delete_translation(
+            params.user_id,
+            &params.word,
+            &params.translation,
+            params.lang.as_ref(),
+        ) {
+            Ok(words) => json_response(&words),
+            Err(e) => {
+                error!("Can't delete translation: {}", e);
+                internal_server_error_response()
+            }
+        },
+        Err(e) => {
+            warn!("Params parse error: {}", e);
+            unauthorized_response()
+        }
+    };
+    resp.headers_mut().insert(
+        "Content-Type",
+        HeaderValue::from_str("application/json; charset=utf-8").unwrap(),
+    );
+
+    Ok(resp)
+}
+
+pub fn order_translations(
+    user_words: Arc<UserWords>,
     req: &Request<Body>,
 ) -> Result<Response<Body>, Error> {
-    let mut resp = match params::user_id(&req) {
-        Ok(user_id) => {
-            let user_w = user_words.read().unwrap();
-            match user_w.list_langs(user_id.user_id) {
+    let mut resp = match params::order_translations(req) {
+        Ok(params) => {
+            match user_words.reorder_translations(params.user_id, &params.word, &params.order) {
+                Ok(words) => json_response(&words),
+                Err(e) => {
+                    error!("Can't order translations: {}", e);
+                    internal_server_error_response()
+                }
+            }
+        }
+        Err(e) => {
+            warn!("Params parse error: {}", e);
+            unauthorized_response()
+        }
+    };
+    resp.headers_mut().insert(
+        "Content-Type",
+        HeaderValue::from_str("application/json; charset=utf-8").unwrap(),
+    );
+
+    Ok(resp)
+}
+
+// Replaces the note, examples, grammar and tags of a word and returns entries of the word
+pub fn edit_word(user_words: Arc<UserWords>, req: &Request<Body>) -> Result<Response<Body>, Error> {
+    let mut resp = match params::word_details(req) {
+        Ok(params) => {
+            let edit = Edit::Replace(Details {
+                note: params.note,
+                examples: params.examples,
+                grammar: params.grammar,
+                tags: params.tags,
+            });
+            match user_words.edit_word(params.user_id, &params.word, &edit) {
                 Ok(words) => json_response(&words),
                 Err(e) => {
-                    error!("Can't get words list: {}", e);
+                    error!("Can't edit word: {}", e);
                     internal_server_error_response()
                 }
             }
@@ -65,6 +184,31 @@ pub fn list_langs(
     Ok(resp)
 }
 
+pub fn list_langs(
+    user_words: Arc<UserWords>,
+    req: &Request<Body>,
+) -> Result<Response<Body>, Error> {
+    let mut resp = match params::user_id(req) {
+        Ok(user_id) => match user_words.list_langs(user_id.user_id) {
+            Ok(words) => json_response(&words),
+            Err(e) => {
+                error!("Can't get words list: {}", e);
+                internal_server_error_response()
+            }
+        },
+        Err(e) => {
+            warn!("Params parse error: {}", e);
+            unauthorized_response()
+        }
+    };
+    resp.headers_mut().insert(
+        "Content-Type",
+        HeaderValue::from_str("application/json; charset=utf-8").unwrap(),
+    );
+
+    Ok(resp)
+}
+
 fn internal_server_error_response() -> Response<Body> {
     Response::builder()
         .status(StatusCode::INTERNAL_SERVER_ERROR)
@@ -74,7 +218,28 @@ fn internal_server_error_response() -> Response<Body> {
         .unwrap()
 }
 
-fn unauthorized_response() -> Response<Body> {
+// The bearer token of the request is the configured one. Without a configured token
+// nothing is authorized. The comparison takes the same time wherever the bytes differ
+pub fn authorized(req: &Request<Body>, token: Option<&str>) -> bool {
+    let token = match token {
+        Some(token) => token,
+        None => return false,
+    };
+    let given = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+pub fn unauthorized_response() -> Response<Body> {
     Response::builder()
         .status(StatusCode::UNAUTHORIZED)
         .body(Body::from(
@@ -92,3 +257,28 @@ pub fn json_response(data: &impl Serialize) -> Response<Body> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::api::authorized;
+
+    use hyper::{Body, Request};
+
+    #[test]
+    fn token() {
+        let req = |auth: Option<&str>| {
+            let mut b = Request::builder().method("POST").uri("/api/words/restore");
+            if let Some(auth) = auth {
+                b = b.header("Authorization", auth);
+            }
+            b.body(Body::empty()).unwrap()
+        };
+        assert!(authorized(&req(Some("Bearer secret")), Some("secret")));
+        assert!(!authorized(&req(Some("Bearer secreT")), Some("secret")));
+        assert!(!authorized(&req(Some("Bearer secret2")), Some("secret")));
+        assert!(!authorized(&req(Some("secret")), Some("secret")));
+        assert!(!authorized(&req(None), Some("secret")));
+        // nothing is allowed without a configured token
+        assert!(!authorized(&req(Some("Bearer ")), None));
+    }
+}
@@ -21,7 +21,7 @@ fn file_response(filename: &str) -> Result<Response<Body>, Error> {
         Ok(content) => {
             let mut resp = Response::new(content.into());
             resp.headers_mut()
-                .insert("Content-Type", path_to_mime(&filename));
+                .insert("Content-Type", path_to_mime(filename));
             resp
         }
         Err(e) => {
@@ -36,7 +36,7 @@ fn file_response(filename: &str) -> Result<Response<Body>, Error> {
     Ok(resp)
 }
 
-fn path_to_mime<'a>(path: &str) -> HeaderValue {
+fn path_to_mime(path: &str) -> HeaderValue {
     let s = path.to_lowercase();
     let default: &str = "text/plain;charset=UTF-8";
     let mut ext = "";
@@ -45,7 +45,7 @@ fn path_to_mime<'a>(path: &str) -> HeaderValue {
             ext = &s[p + 1..]
         }
     }
-    if ext == "" {
+    if ext.is_empty() {
         return HeaderValue::from_str(default).unwrap();
     }
     let mime: String = match ext {
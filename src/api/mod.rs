@@ -1,23 +1,38 @@
+#[allow(clippy::module_inception)]
 pub mod api;
 pub mod front;
 pub mod params;
 
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 use crate::UserWords;
 
 use crate::api::front::front_static_files;
 use hyper::{Body, Error, Method, Request, Response, StatusCode};
 
-// TODO: tokio RWLock
+// user_id is taken from the request as is. Reads are open, changes need the LW_API_TOKEN
+// secret in "Authorization: Bearer <token>" and are refused when it isn't set
 pub async fn router(
     req: Request<Body>,
-    user_h: Arc<RwLock<UserWords>>,
+    user_h: Arc<UserWords>,
+    token: Option<Arc<str>>,
 ) -> Result<Response<Body>, Error> {
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/") => front::index(),
         (&Method::GET, "/api/words") => api::list_words(user_h, &req),
         (&Method::GET, "/api/langs") => api::list_langs(user_h, &req),
+        (method, path)
+            if method != Method::GET
+                && path.starts_with("/api/")
+                && !api::authorized(&req, token.as_deref()) =>
+        {
+            Ok(api::unauthorized_response())
+        }
+        (&Method::POST, "/api/words/restore") => api::restore_word(user_h, &req),
+        (&Method::POST, "/api/words/translations") => api::add_translation(user_h, &req),
+        (&Method::DELETE, "/api/words/translations") => api::delete_translation(user_h, &req),
+        (&Method::POST, "/api/words/translations/order") => api::order_translations(user_h, &req),
+        (&Method::PUT, "/api/words/details") => api::edit_word(user_h, &req),
         _ => {
             if req.method() == Method::GET {
                 front_static_files(req.uri().path())
@@ -1,8 +1,11 @@
 use std::error;
 use std::fmt;
 
+use crate::translate::{Lang, LangParseError};
+
 use hyper::{Body, Request};
-use serde::Deserialize;
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer};
 use serde_qs;
 
 #[derive(Deserialize)]
@@ -10,6 +13,64 @@ pub struct UserId {
     pub user_id: i64,
 }
 
+#[derive(Deserialize)]
+pub struct RestoreWord {
+    pub user_id: i64,
+    // the last deleted words if None
+    pub word: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct AddTranslation {
+    pub user_id: i64,
+    pub word: String,
+    pub translation: String,
+    #[serde(deserialize_with = "lang")]
+    pub lang: Lang,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteTranslation {
+    pub user_id: i64,
+    pub word: String,
+    pub translation: String,
+    // in all languages if None
+    #[serde(default, deserialize_with = "optional_lang")]
+    pub lang: Option<Lang>,
+}
+
+#[derive(Deserialize)]
+pub struct OrderTranslations {
+    pub user_id: i64,
+    pub word: String,
+    // order[0]=first&order[1]=second
+    pub order: Vec<String>,
+}
+
+// All details of a word, the ones not given are removed
+#[derive(Deserialize)]
+pub struct WordDetails {
+    pub user_id: i64,
+    pub word: String,
+    pub note: Option<String>,
+    // examples[0]=first&examples[1]=second, the same for tags
+    #[serde(default)]
+    pub examples: Vec<String>,
+    pub grammar: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn lang<'de, D: Deserializer<'de>>(d: D) -> Result<Lang, D::Error> {
+    let s = String::deserialize(d)?;
+    s.parse()
+        .map_err(|e: LangParseError| de::Error::custom(e.description))
+}
+
+fn optional_lang<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Lang>, D::Error> {
+    lang(d).map(Some)
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct Error {
     description: String,
@@ -37,6 +98,30 @@ impl error::Error for Error {
 }
 
 pub fn user_id(req: &Request<Body>) -> Result<UserId, Box<dyn error::Error>> {
+    query(req)
+}
+
+pub fn restore_word(req: &Request<Body>) -> Result<RestoreWord, Box<dyn error::Error>> {
+    query(req)
+}
+
+pub fn add_translation(req: &Request<Body>) -> Result<AddTranslation, Box<dyn error::Error>> {
+    query(req)
+}
+
+pub fn delete_translation(req: &Request<Body>) -> Result<DeleteTranslation, Box<dyn error::Error>> {
+    query(req)
+}
+
+pub fn order_translations(req: &Request<Body>) -> Result<OrderTranslations, Box<dyn error::Error>> {
+    query(req)
+}
+
+pub fn word_details(req: &Request<Body>) -> Result<WordDetails, Box<dyn error::Error>> {
+    query(req)
+}
+
+fn query<T: DeserializeOwned>(req: &Request<Body>) -> Result<T, Box<dyn error::Error>> {
     let q = match req.uri().query() {
         Some(q) => q,
         None => {
@@ -45,7 +130,7 @@ pub fn user_id(req: &Request<Body>) -> Result<UserId, Box<dyn error::Error>> {
             }))
         }
     };
-    let u_id: UserId = serde_qs::from_str(q)?;
+    let params: T = serde_qs::from_str(q)?;
 
-    Ok(u_id)
+    Ok(params)
 }
@@ -7,36 +7,190 @@ mod user;
 use log::{error, info};
 use std::env;
 use std::net::{SocketAddr, SocketAddrV4};
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use storage::Backend;
 use user::user::UserWords;
 
-use env_logger;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::Server;
+use tokio::signal::unix::{signal, SignalKind};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     //export RUST_LOG=info
     env_logger::init();
-    let host = env::var("LW_HOST").expect("No LW_HOST");
-    let telegram_token = env::var("LW_TELEGRAM").expect("No LW_TELEGRAM");
-    let translate_token = env::var("LW_TRANSLATE").expect("No LW_TRANSLATE");
     let db_path = env::var("LW_DB").expect("No LW_DB");
+    // json (default), sqlite or memory
+    let storage_kind = env::var("LW_STORAGE").unwrap_or_else(|_| "json".to_string());
+    // sync (default) or batched, only for json
+    let durability: storage::Durability = env::var("LW_DURABILITY")
+        .unwrap_or_else(|_| "sync".to_string())
+        .parse()
+        .expect("Invalid LW_DURABILITY");
+    let flush_interval: u64 = env::var("LW_FLUSH_INTERVAL")
+        .unwrap_or_else(|_| "5".to_string())
+        .parse()
+        .expect("Invalid LW_FLUSH_INTERVAL");
+    // snapshots are taken only if LW_BACKUP_DIR is set
+    let backup_dir = env::var("LW_BACKUP_DIR").ok();
+    let backup_interval: u64 = env::var("LW_BACKUP_INTERVAL")
+        .unwrap_or_else(|_| "3600".to_string())
+        .parse()
+        .expect("Invalid LW_BACKUP_INTERVAL");
+    let backup_keep: usize = env::var("LW_BACKUP_KEEP")
+        .unwrap_or_else(|_| "48".to_string())
+        .parse()
+        .expect("Invalid LW_BACKUP_KEEP");
 
-    let addr = SocketAddrV4::from(host.parse().expect("Invalid host address"));
+    // deleted words are kept in the trash for this number of days
+    let trash_days: u64 = env::var("LW_TRASH_DAYS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse()
+        .expect("Invalid LW_TRASH_DAYS");
 
-    let storage = match storage::Storage::new(&db_path) {
-        Ok(storage) => Arc::new(RwLock::new(storage)),
-        Err(e) => {
-            panic!("Can't open DB {}", e);
+    let args: Vec<String> = env::args().collect();
+    // lengwurds import-json db.json: one-shot import of the JSON DB into sqlite LW_DB
+    if args.len() > 2 && args[1] == "import-json" {
+        let stor = storage::sqlite::SqliteStorage::new(&db_path)?;
+        let n = stor.import_json(&args[2])?;
+        info!("Imported {} users from {} to {}", n, args[2], db_path);
+        return Ok(());
+    }
+    // lengwurds restore backup.json: replaces LW_DB with a validated snapshot, the server must be stopped.
+    // The current state is saved as a new snapshot first, so the restore can be undone
+    if args.len() > 2 && args[1] == "restore" {
+        let (stor, json_storage) = open_storage(&storage_kind, &db_path, durability);
+        storage::backup::validate(&args[2])?;
+        let dir = match &backup_dir {
+            Some(dir) => dir.clone(),
+            None => match Path::new(&args[2]).parent().and_then(|p| p.to_str()) {
+                Some(dir) if !dir.is_empty() => dir.to_string(),
+                _ => ".".to_string(),
+            },
+        };
+        let before = storage::backup::create(stor.as_ref(), &dir, unix_now())?;
+        info!("Current DB saved to {}", before);
+        let n = storage::backup::restore(stor.as_ref(), &args[2])?;
+        if let Some(stor) = json_storage {
+            storage::flush(&stor)?;
         }
+        info!("Restored {} users from {} to {}", n, args[2], db_path);
+        return Ok(());
+    }
+
+    let host = env::var("LW_HOST").expect("No LW_HOST");
+    let telegram_token = env::var("LW_TELEGRAM").expect("No LW_TELEGRAM");
+    // the offline dictionary and self-hosted libre servers work without a key
+    let translate_token = env::var("LW_TRANSLATE").unwrap_or_default();
+    // google (default), deepl, libre, yandex or dictionary.
+    // A comma separated list is a fallback chain: "google,libre,dictionary"
+    let translate_providers: Vec<translate::Provider> = env::var("LW_TRANSLATOR")
+        .unwrap_or_else(|_| "google".to_string())
+        .split(',')
+        .map(|p| p.parse().expect("Invalid LW_TRANSLATOR"))
+        .collect();
+    // errors in a row before a provider is skipped and seconds to wait before retrying it
+    let breaker = translate::fallback::BreakerConfig {
+        failures: env::var("LW_BREAKER_FAILURES")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .expect("Invalid LW_BREAKER_FAILURES"),
+        cooldown: env::var("LW_BREAKER_COOLDOWN")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .expect("Invalid LW_BREAKER_COOLDOWN"),
     };
-    let user_words = Arc::new(RwLock::new(UserWords::new(
-        storage.clone(),
-        translate::google::Client::new(&translate_token),
-    )));
+    // API address of deepl or a self-hosted libre server
+    let translate_url = env::var("LW_TRANSLATE_URL").ok();
+    // yandex cloud folder, not needed with a service account key
+    let translate_folder = env::var("LW_TRANSLATE_FOLDER").ok();
+    // dictionary files: "wiktextract:words.jsonl,stardict:en-ru:dict.ifo"
+    let dictionaries: Vec<String> = env::var("LW_DICTIONARIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .collect();
+    // translations are cached in memory, LW_CACHE keeps them between restarts
+    let cache_ttl: u64 = env::var("LW_CACHE_DAYS")
+        .unwrap_or_else(|_| "30".to_string())
+        .parse()
+        .expect("Invalid LW_CACHE_DAYS");
+    let cache_size: usize = env::var("LW_CACHE_SIZE")
+        .unwrap_or_else(|_| "100000".to_string())
+        .parse()
+        .expect("Invalid LW_CACHE_SIZE");
+    let translate_cache = Some(Arc::new(
+        translate::cache::Store::open(
+            env::var("LW_CACHE").ok().as_deref(),
+            cache_ttl * storage::schedule::DAY,
+            cache_size,
+        )
+        .expect("Can't open translate cache"),
+    ));
+    let translator = translate::client(&translate::Config {
+        providers: translate_providers,
+        breaker,
+        token: translate_token,
+        url: translate_url,
+        folder: translate_folder,
+        dictionaries,
+        cache: translate_cache.clone(),
+    })
+    .expect("Invalid translate provider config");
+
+    // seconds between updates of the provider language list
+    let langs_interval: u64 = env::var("LW_LANGS_INTERVAL")
+        .unwrap_or_else(|_| "86400".to_string())
+        .parse()
+        .expect("Invalid LW_LANGS_INTERVAL");
+
+    let addr = host.parse::<SocketAddrV4>().expect("Invalid host address");
+    // API calls that change words need it, without it they are refused
+    let api_token: Option<Arc<str>> = env::var("LW_API_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .map(Arc::from);
+    if api_token.is_none() {
+        info!("No LW_API_TOKEN, API changes are off");
+    }
+
+    let (storage, json_storage) = open_storage(&storage_kind, &db_path, durability);
+    let user_words = Arc::new(UserWords::new(storage.clone(), translator));
     let telegram_user_words = user_words.clone();
+    let purge_user_words = user_words.clone();
+    let langs_user_words = user_words.clone();
+    std::thread::spawn(move || {
+        user::user::langs_loop(langs_user_words, Duration::from_secs(langs_interval))
+    });
+    std::thread::spawn(move || user::user::purge_loop(purge_user_words, trash_days));
+
+    let mut flusher = None;
+    if let (Some(stor), storage::Durability::Batched) = (json_storage.clone(), durability) {
+        let (stop, stopped) = mpsc::channel();
+        let handle = std::thread::spawn(move || {
+            storage::flush_loop(stor, Duration::from_secs(flush_interval), stopped)
+        });
+        flusher = Some((stop, handle));
+    }
+    if let Some(dir) = backup_dir {
+        let stor = storage.clone();
+        std::thread::spawn(move || {
+            storage::backup::backup_loop(
+                stor,
+                dir,
+                Duration::from_secs(backup_interval),
+                backup_keep,
+            )
+        });
+    }
+
+    if let Some(store) = translate_cache.clone() {
+        std::thread::spawn(move || translate::cache::save_loop(store, Duration::from_secs(60)));
+    }
 
     std::thread::spawn(|| {
         telegram::updates::updates_processing(telegram_user_words, telegram_token)
@@ -44,13 +198,86 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let make_svc = make_service_fn(move |_conn| {
         let user_h = user_words.clone();
-        async { Ok::<_, hyper::Error>(service_fn(move |req| api::router(req, user_h.clone()))) }
+        let token = api_token.clone();
+        async {
+            Ok::<_, hyper::Error>(service_fn(move |req| {
+                api::router(req, user_h.clone(), token.clone())
+            }))
+        }
     });
-    let server = Server::bind(&SocketAddr::from(addr)).serve(make_svc);
+    let server = Server::bind(&SocketAddr::from(addr))
+        .serve(make_svc)
+        .with_graceful_shutdown(shutdown_signal());
     info!("Start server: {}", host);
     if let Err(e) = server.await {
         error!("Server error: {}", e);
     }
+    // the loop may be in the middle of a flush, the last one must come after it
+    if let Some((stop, handle)) = flusher {
+        drop(stop);
+        if handle.join().is_err() {
+            error!("Flush thread panicked");
+        }
+    }
+    if let Some(stor) = json_storage {
+        info!("Flush DB before exit");
+        if let Err(e) = storage::flush(&stor) {
+            error!("Can't flush DB: {}", e);
+        }
+    }
+    if let Some(store) = translate_cache {
+        if let Err(e) = store.save() {
+            error!("Can't save translate cache: {}", e);
+        }
+    }
 
     Ok(())
 }
+
+type Storages = (
+    Arc<dyn Backend + Send + Sync>,
+    Option<Arc<storage::Storage>>,
+);
+
+// The storage of LW_STORAGE kind and the same storage if it is the JSON one, it needs flushing
+fn open_storage(kind: &str, db_path: &str, durability: storage::Durability) -> Storages {
+    let mut json_storage: Option<Arc<storage::Storage>> = None;
+    let storage: Arc<dyn Backend + Send + Sync> = match kind {
+        "sqlite" => match storage::sqlite::SqliteStorage::new(db_path) {
+            Ok(storage) => Arc::new(storage),
+            Err(e) => {
+                panic!("Can't open DB {}", e);
+            }
+        },
+        "json" => match storage::Storage::new(db_path, durability) {
+            Ok(storage) => {
+                let storage = Arc::new(storage);
+                json_storage = Some(storage.clone());
+                storage
+            }
+            Err(e) => {
+                panic!("Can't open DB {}", e);
+            }
+        },
+        "memory" => Arc::new(storage::memory::MemoryStorage::new()),
+        _ => panic!("Unknown LW_STORAGE: {}", kind),
+    };
+
+    (storage, json_storage)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// SIGINT or SIGTERM from systemd
+async fn shutdown_signal() {
+    let mut term = signal(SignalKind::terminate()).expect("Can't listen SIGTERM");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = term.recv() => {},
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::storage::{migration, write_atomic, Backend, User};
+
+use log::{error, info};
+
+// Snapshots are named lengwurds-<unix time>.json, so the names sort by time
+const PREFIX: &str = "lengwurds-";
+const SUFFIX: &str = ".json";
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Error {
+    description: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Backup: {}", &self.description)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+fn error(description: &str) -> Box<dyn error::Error> {
+    Box::new(Error {
+        description: description.to_string(),
+    })
+}
+
+// Writes a snapshot of the whole DB in the JSON document format, whatever the backend is
+pub fn create(storage: &dyn Backend, dir: &str, now: u64) -> Result<String, Box<dyn error::Error>> {
+    fs::create_dir_all(dir)?;
+    let path = Path::new(dir).join(format!("{}{:010}{}", PREFIX, now, SUFFIX));
+    let path = match path.to_str() {
+        Some(p) => p.to_string(),
+        None => return Err(error("Invalid backup path")),
+    };
+    let data = migration::dump(&storage.list()?)?;
+    write_atomic(&path, data.as_bytes())?;
+
+    Ok(path)
+}
+
+// Snapshots in the directory, the oldest first
+pub fn list(dir: &str) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let mut names: Vec<String> = vec![];
+    for entry in fs::read_dir(dir)? {
+        let name = match entry?.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        let ts = match name
+            .strip_prefix(PREFIX)
+            .and_then(|n| n.strip_suffix(SUFFIX))
+        {
+            Some(ts) => ts,
+            None => continue,
+        };
+        if ts.parse::<u64>().is_ok() {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    Ok(names
+        .iter()
+        .filter_map(|n| Path::new(dir).join(n).to_str().map(|p| p.to_string()))
+        .collect())
+}
+
+// Removes all but the newest `keep` snapshots, returns the number of removed files
+pub fn prune(dir: &str, keep: usize) -> Result<usize, Box<dyn error::Error>> {
+    let paths = list(dir)?;
+    let n = paths.len().saturating_sub(keep);
+    for path in paths.iter().take(n) {
+        fs::remove_file(path)?;
+    }
+
+    Ok(n)
+}
+
+// Loads a snapshot and checks it can be swapped in
+pub fn validate(path: &str) -> Result<Vec<User>, Box<dyn error::Error>> {
+    let raw_json = fs::read_to_string(path)?;
+    // an empty DB is a valid document, but never an empty file
+    if raw_json.trim().is_empty() {
+        return Err(error("Empty snapshot"));
+    }
+    let users = migration::load(&raw_json)?;
+    let mut ids: HashSet<i64> = HashSet::new();
+    for user in users.iter() {
+        if !ids.insert(user.id) {
+            return Err(error(&format!("Duplicate user {}", user.id)));
+        }
+        if user.translates.iter().any(|tr| tr.word.word.is_empty()) {
+            return Err(error(&format!("Empty word of user {}", user.id)));
+        }
+    }
+
+    Ok(users)
+}
+
+// Replaces the content of the storage with the snapshot in one step.
+// Users that aren't in the snapshot are deleted. Returns the number of restored users
+pub fn restore(storage: &dyn Backend, path: &str) -> Result<usize, Box<dyn error::Error>> {
+    let users = validate(path)?;
+    let n = users.len();
+    storage.replace_all(users)?;
+
+    Ok(n)
+}
+
+pub fn backup_loop(
+    storage: Arc<dyn Backend + Send + Sync>,
+    dir: String,
+    interval: Duration,
+    keep: usize,
+) {
+    loop {
+        sleep(interval);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        match create(storage.as_ref(), &dir, now) {
+            Ok(path) => info!("Backup saved: {}", path),
+            Err(e) => {
+                error!("Can't save backup: {}", e);
+                continue;
+            }
+        }
+        if let Err(e) = prune(&dir, keep) {
+            error!("Can't prune backups: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::storage::backup::{create, list, prune, restore, validate};
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::strategy::AddLang;
+    use crate::storage::{Backend, User};
+
+    #[test]
+    fn create_prune_restore() {
+        let dir = env::temp_dir().join(format!("lengwurds_backup_test_{}", std::process::id()));
+        let dir = dir.to_str().unwrap();
+        let _ = fs::remove_dir_all(dir);
+        let stor = MemoryStorage::new();
+        let en = AddLang {
+            lang: "en".parse().unwrap(),
+        };
+        stor.upsert(1, &en).unwrap();
+
+        let first = create(&stor, dir, 100).unwrap();
+        stor.upsert(2, &en).unwrap();
+        create(&stor, dir, 200).unwrap();
+        let last = create(&stor, dir, 300).unwrap();
+        fs::write(format!("{}/notes.txt", dir), "keep me").unwrap();
+        assert_eq!(3, list(dir).unwrap().len());
+        assert_eq!(first, list(dir).unwrap()[0]);
+
+        assert_eq!(1, prune(dir, 2).unwrap());
+        assert_eq!(2, list(dir).unwrap().len());
+        assert!(fs::metadata(&first).is_err());
+        assert!(fs::metadata(format!("{}/notes.txt", dir)).is_ok());
+
+        // accidental delete of a user and a new one after the snapshot
+        stor.replace_all(vec![stor.get(2).unwrap().unwrap()])
+            .unwrap();
+        stor.upsert(3, &en).unwrap();
+        assert_eq!(2, restore(&stor, &last).unwrap());
+        let mut expect = vec![User::new(1), User::new(2)];
+        for u in expect.iter_mut() {
+            u.langs.push("en".parse().unwrap());
+        }
+        assert_eq!(expect, stor.list().unwrap());
+
+        let broken = format!("{}/broken.json", dir);
+        fs::write(&broken, "").unwrap();
+        assert!(validate(&broken).is_err());
+        fs::write(&broken, r#"{"version": 2, "users": [{"id": 1"#).unwrap();
+        assert!(validate(&broken).is_err());
+        fs::write(
+            &broken,
+            r#"[{"id": 1, "langs": [], "translates": []}, {"id": 1, "langs": [], "translates": []}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            "Backup: Duplicate user 1",
+            validate(&broken).unwrap_err().to_string()
+        );
+        assert!(restore(&stor, &broken).is_err());
+        assert_eq!(expect, stor.list().unwrap());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
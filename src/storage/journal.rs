@@ -0,0 +1,131 @@
+use std::error;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+
+use crate::storage::User;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+// One line of the journal: the state of a user after a strategy was applied,
+// None if the user was deleted. Replaying the same entry twice is harmless
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Entry {
+    pub id: i64,
+    pub user: Option<User>,
+}
+
+pub struct Journal {
+    path: String,
+    file: fs::File,
+}
+
+impl Journal {
+    // Opens (or creates) the journal and returns entries that weren't compacted yet
+    pub fn open(path: &str) -> Result<(Journal, Vec<Entry>), Box<dyn error::Error>> {
+        let mut entries: Vec<Entry> = vec![];
+        match fs::File::open(path) {
+            Ok(f) => {
+                for line in io::BufReader::new(f).lines() {
+                    let line = line?;
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str(&line) {
+                        Ok(entry) => entries.push(entry),
+                        // a crash in the middle of append leaves a torn last line
+                        Err(e) => {
+                            warn!("Skip broken journal entry in {}: {}", path, e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                if io::ErrorKind::NotFound != e.kind() {
+                    return Err(Box::new(e));
+                }
+            }
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok((
+            Journal {
+                path: path.to_string(),
+                file,
+            },
+            entries,
+        ))
+    }
+
+    pub fn append(&mut self, entry: &Entry) -> Result<(), Box<dyn error::Error>> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()?;
+
+        Ok(())
+    }
+
+    // Must be called only after the compacted DB was saved
+    pub fn truncate(&mut self) -> Result<(), Box<dyn error::Error>> {
+        self.file = fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.file.sync_all()?;
+        self.file = fs::OpenOptions::new().append(true).open(&self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    use crate::storage::journal::{Entry, Journal};
+    use crate::storage::User;
+
+    #[test]
+    fn replay() {
+        let path = env::temp_dir().join(format!("lengwurds_journal_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        let entries = vec![
+            Entry {
+                id: 1,
+                user: Some(User::new(1)),
+            },
+            Entry { id: 2, user: None },
+        ];
+        {
+            let (mut j, replay) = Journal::open(path).unwrap();
+            assert!(replay.is_empty());
+            for e in entries.iter() {
+                j.append(e).unwrap();
+            }
+        }
+        // torn write
+        fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(b"{\"id\": 3, \"us")
+            .unwrap();
+        {
+            let (mut j, replay) = Journal::open(path).unwrap();
+            assert_eq!(entries, replay);
+            j.truncate().unwrap();
+        }
+        let (_, replay) = Journal::open(path).unwrap();
+        assert!(replay.is_empty());
+        fs::remove_file(path).unwrap();
+    }
+}
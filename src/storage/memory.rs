@@ -0,0 +1,92 @@
+use std::error;
+
+use crate::storage::shards;
+use crate::storage::shards::Shards;
+use crate::storage::{strategy, Backend, User};
+
+// Keeps everything in memory, nothing survives a restart. Useful for tests and local runs
+pub struct MemoryStorage {
+    users: Shards,
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        MemoryStorage {
+            users: Shards::new(vec![]),
+        }
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        MemoryStorage::new()
+    }
+}
+
+impl Backend for MemoryStorage {
+    fn get(&self, user_id: i64) -> Result<Option<User>, Box<dyn error::Error>> {
+        Ok(self.users.get(user_id))
+    }
+
+    fn upsert(
+        &self,
+        user_id: i64,
+        strat: &dyn strategy::UserUpdateStrategy,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let mut shard = self.users.shard(user_id).write().unwrap();
+        let user = match shard.get(&user_id) {
+            Some(user) => strat.apply(user),
+            None => strat.apply(&User::new(user_id)),
+        };
+        shard.insert(user_id, user);
+
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<User>, Box<dyn error::Error>> {
+        Ok(self.users.list())
+    }
+
+    fn replace_all(&self, users: Vec<User>) -> Result<(), Box<dyn error::Error>> {
+        shards::fill(&mut self.users.write_all(), users);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::strategy::AddLang;
+    use crate::storage::{Backend, User};
+
+    #[test]
+    fn upsert() {
+        let stor = MemoryStorage::new();
+        assert_eq!(None, stor.get(1).unwrap());
+
+        for id in [2, 1] {
+            stor.upsert(
+                id,
+                &AddLang {
+                    lang: "en".parse().unwrap(),
+                },
+            )
+            .unwrap();
+        }
+        let mut expect = User::new(1);
+        expect.langs.push("en".parse().unwrap());
+        assert_eq!(Some(expect.clone()), stor.get(1).unwrap());
+        assert_eq!(
+            vec![1, 2],
+            stor.list()
+                .unwrap()
+                .iter()
+                .map(|u| u.id)
+                .collect::<Vec<i64>>()
+        );
+
+        stor.replace_all(vec![expect.clone()]).unwrap();
+        assert_eq!(vec![expect], stor.list().unwrap());
+    }
+}
@@ -0,0 +1,217 @@
+use std::error;
+use std::fmt;
+
+use crate::storage::schedule::Schedule;
+use crate::storage::User;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+// Version of the DB document written by this build.
+// To change the format bump it and append a step to MIGRATIONS
+pub const VERSION: u64 = 3;
+
+type Migration = fn(Value) -> Result<Value, Box<dyn error::Error>>;
+
+// MIGRATIONS[i] upgrades a document of version i to version i + 1
+const MIGRATIONS: [Migration; VERSION as usize] = [v0_to_v1, v1_to_v2, v2_to_v3];
+
+#[derive(Deserialize, Serialize)]
+struct Document {
+    version: u64,
+    users: Vec<User>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Error {
+    description: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Migration: {}", &self.description)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+fn error(description: &str) -> Box<dyn error::Error> {
+    Box::new(Error {
+        description: description.to_string(),
+    })
+}
+
+// Version 0 is the legacy bare array of users
+fn version(doc: &Value) -> Result<u64, Box<dyn error::Error>> {
+    match doc {
+        Value::Array(_) => Ok(0),
+        Value::Object(o) => match o.get("version").and_then(|v| v.as_u64()) {
+            Some(v) => Ok(v),
+            None => Err(error("No document version")),
+        },
+        _ => Err(error("Unknown document format")),
+    }
+}
+
+pub fn migrate(mut doc: Value) -> Result<Value, Box<dyn error::Error>> {
+    let from = version(&doc)?;
+    if from > VERSION {
+        return Err(error(&format!(
+            "DB version {} is newer than supported {}",
+            from, VERSION
+        )));
+    }
+    for m in MIGRATIONS.iter().skip(from as usize) {
+        doc = m(doc)?;
+    }
+
+    Ok(doc)
+}
+
+pub fn load(raw_json: &str) -> Result<Vec<User>, Box<dyn error::Error>> {
+    if raw_json.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let doc = migrate(serde_json::from_str(raw_json)?)?;
+    let doc: Document = serde_json::from_value(doc)?;
+
+    Ok(doc.users)
+}
+
+pub fn dump(users: &[User]) -> Result<String, Box<dyn error::Error>> {
+    let doc = Document {
+        version: VERSION,
+        users: users.to_vec(),
+    };
+
+    Ok(serde_json::to_string(&doc)?)
+}
+
+// Wrap the bare array into a versioned document
+fn v0_to_v1(doc: Value) -> Result<Value, Box<dyn error::Error>> {
+    let mut o = Map::new();
+    o.insert("version".to_string(), Value::from(1));
+    o.insert("users".to_string(), doc);
+
+    Ok(Value::Object(o))
+}
+
+// Fill review state of words added before spaced repetition
+fn v1_to_v2(mut doc: Value) -> Result<Value, Box<dyn error::Error>> {
+    let schedule = serde_json::to_value(Schedule::default())?;
+    let users = match doc.get_mut("users").and_then(|u| u.as_array_mut()) {
+        Some(users) => users,
+        None => return Err(error("No users")),
+    };
+    for user in users.iter_mut() {
+        let trs = match user.get_mut("translates").and_then(|t| t.as_array_mut()) {
+            Some(trs) => trs,
+            None => continue,
+        };
+        for tr in trs.iter_mut().filter_map(|t| t.as_object_mut()) {
+            tr.entry("last_seen").or_insert_with(|| Value::from(0));
+            tr.entry("schedule").or_insert_with(|| schedule.clone());
+        }
+    }
+    doc["version"] = Value::from(2);
+
+    Ok(doc)
+}
+
+// Empty trash for users from before deleted words were kept
+fn v2_to_v3(mut doc: Value) -> Result<Value, Box<dyn error::Error>> {
+    let users = match doc.get_mut("users").and_then(|u| u.as_array_mut()) {
+        Some(users) => users,
+        None => return Err(error("No users")),
+    };
+    for user in users.iter_mut().filter_map(|u| u.as_object_mut()) {
+        user.entry("trash").or_insert_with(|| Value::Array(vec![]));
+    }
+    doc["version"] = Value::from(3);
+
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::migration::{dump, load, migrate, v0_to_v1, v1_to_v2, v2_to_v3, VERSION};
+    use crate::storage::schedule::Schedule;
+    use crate::storage::User;
+    use serde_json::json;
+
+    #[test]
+    fn migrate_v0_to_v1() {
+        let doc = json!([{"id": 1, "langs": [], "translates": []}]);
+        assert_eq!(
+            json!({"version": 1, "users": [{"id": 1, "langs": [], "translates": []}]}),
+            v0_to_v1(doc).unwrap()
+        );
+    }
+
+    #[test]
+    fn migrate_v1_to_v2() {
+        let schedule = serde_json::to_value(Schedule::default()).unwrap();
+        let doc = json!({"version": 1, "users": [{"id": 1, "langs": [], "translates": [
+            {"word": {"word": "word", "lang": {"lang": "en"}}, "translates": []},
+            {"word": {"word": "door", "lang": {"lang": "en"}}, "translates": [], "last_seen": 5,
+                "schedule": {"ease": 2.0, "interval": 6, "repetitions": 2, "due": 10}}
+        ]}]});
+        assert_eq!(
+            json!({"version": 2, "users": [{"id": 1, "langs": [], "translates": [
+                {"word": {"word": "word", "lang": {"lang": "en"}}, "translates": [],
+                    "last_seen": 0, "schedule": schedule},
+                {"word": {"word": "door", "lang": {"lang": "en"}}, "translates": [], "last_seen": 5,
+                    "schedule": {"ease": 2.0, "interval": 6, "repetitions": 2, "due": 10}}
+            ]}]}),
+            v1_to_v2(doc).unwrap()
+        );
+        assert!(v1_to_v2(json!({"version": 1})).is_err());
+    }
+
+    #[test]
+    fn migrate_v2_to_v3() {
+        let doc = json!({"version": 2, "users": [
+            {"id": 1, "langs": [], "translates": []},
+            {"id": 2, "langs": [], "translates": [], "trash": [{"translate": {}, "deleted": 5}]}
+        ]});
+        assert_eq!(
+            json!({"version": 3, "users": [
+                {"id": 1, "langs": [], "translates": [], "trash": []},
+                {"id": 2, "langs": [], "translates": [], "trash": [{"translate": {}, "deleted": 5}]}
+            ]}),
+            v2_to_v3(doc).unwrap()
+        );
+        assert!(v2_to_v3(json!({"version": 2})).is_err());
+    }
+
+    #[test]
+    fn migrate_all() {
+        let doc = migrate(json!([])).unwrap();
+        assert_eq!(json!({"version": VERSION, "users": []}), doc);
+        assert_eq!(doc, migrate(doc.clone()).unwrap());
+        assert_eq!(
+            "Migration: DB version 100 is newer than supported 3",
+            migrate(json!({"version": 100, "users": []}))
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(migrate(json!({"users": []})).is_err());
+    }
+
+    #[test]
+    fn load_dump() {
+        assert!(load("").unwrap().is_empty());
+        let legacy = r#"[{"id": 1, "langs": [{"lang": "en"}], "translates": []}]"#;
+        let mut expect = User::new(1);
+        expect.langs.push("en".parse().unwrap());
+        assert_eq!(vec![expect.clone()], load(legacy).unwrap());
+        assert_eq!(
+            vec![expect.clone()],
+            load(&dump(&[expect]).unwrap()).unwrap()
+        );
+    }
+}
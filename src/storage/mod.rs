@@ -1,19 +1,45 @@
+pub mod backup;
+pub mod journal;
+pub mod memory;
+pub mod migration;
+pub mod schedule;
+pub mod shards;
+pub mod sqlite;
 pub mod strategy;
 
+use std::collections::HashMap;
 use std::error;
 use std::fmt;
 use std::fs;
 use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
+use crate::storage::schedule::Schedule;
 use crate::translate::Lang;
 
+use log::error;
 use serde::{Deserialize, Serialize};
-use serde_json;
 
 #[derive(Deserialize, Serialize, Clone, PartialEq, Debug, Eq, Hash)]
 pub struct Word {
     pub word: String,
     pub lang: Lang,
+    // part of speech of a translation, if the provider knows it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pos: Option<String>,
+    // a translation typed by the user, machine translations never replace it
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub manual: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
 }
 
 impl fmt::Display for Word {
@@ -28,17 +54,81 @@ pub struct Translate {
     pub word: Word,
     #[serde(default)]
     pub last_seen: u64,
+    #[serde(default)]
+    pub schedule: Schedule,
+    #[serde(default, skip_serializing_if = "Details::is_empty")]
+    pub details: Details,
+    // translations deleted by the user, a new translation doesn't bring them back
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<Word>,
+}
+
+// What the user adds to a word to remember it
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug, Default)]
+pub struct Details {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    // sentences with the word
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<String>,
+    // gender or part of speech of the word itself, free text like "noun, f"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grammar: Option<String>,
+    // lowercase and without "#", see tag
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Details {
+    pub fn is_empty(&self) -> bool {
+        self == &Details::default()
+    }
+}
+
+// "#Verbs" and "verbs" are one tag
+pub fn tag(s: &str) -> String {
+    s.trim().trim_start_matches('#').to_lowercase()
 }
 
 impl fmt::Display for Translate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut s = format!(
-            "{}\t{}\n",
-            self.word.lang.lang.to_uppercase(),
-            self.word.word,
-        );
+        let mut s = match &self.details.grammar {
+            Some(grammar) => format!(
+                "{}\t{} ({})\n",
+                self.word.lang.lang.to_uppercase(),
+                self.word.word,
+                grammar
+            ),
+            None => format!(
+                "{}\t{}\n",
+                self.word.lang.lang.to_uppercase(),
+                self.word.word,
+            ),
+        };
         for w in &self.translates {
-            s.push_str(format!("{}\t{}\n", w.lang.lang.to_uppercase(), w.word).as_str());
+            match &w.pos {
+                Some(pos) => s.push_str(
+                    format!("{}\t{} ({})\n", w.lang.lang.to_uppercase(), w.word, pos).as_str(),
+                ),
+                None => {
+                    s.push_str(format!("{}\t{}\n", w.lang.lang.to_uppercase(), w.word).as_str())
+                }
+            }
+        }
+        if let Some(note) = &self.details.note {
+            s.push_str(format!("Note: {}\n", note).as_str())
+        }
+        for (i, e) in self.details.examples.iter().enumerate() {
+            s.push_str(format!("{}. {}\n", i + 1, e).as_str())
+        }
+        if !self.details.tags.is_empty() {
+            let tags: Vec<String> = self
+                .details
+                .tags
+                .iter()
+                .map(|t| format!("#{}", t))
+                .collect();
+            s.push_str(format!("{}\n", tags.join(" ")).as_str())
         }
 
         write!(f, "{}", s)
@@ -46,16 +136,79 @@ impl fmt::Display for Translate {
 }
 
 impl Translate {
+    // A word never reviewed, without details
+    pub fn new(word: Word, translates: Vec<Word>) -> Translate {
+        Translate {
+            translates,
+            word,
+            last_seen: 0,
+            schedule: Schedule::default(),
+            details: Details::default(),
+            rejected: vec![],
+        }
+    }
+
+    // "#tag" looks only for the tag, other patterns also search notes and examples
     pub fn contains(&self, pattern: &str) -> bool {
-        if self.word.word.contains(pattern) {
+        if let Some(t) = pattern.trim().strip_prefix('#') {
+            return self.details.tags.contains(&tag(t));
+        }
+        let pattern = pattern.to_lowercase();
+        if self.word.word.to_lowercase().contains(&pattern) {
             return true;
         }
         for w in self.translates.iter() {
-            if w.word.contains(pattern) {
+            if w.word.to_lowercase().contains(&pattern) {
                 return true;
             }
         }
-        false
+        let d = &self.details;
+        d.note
+            .iter()
+            .chain(d.examples.iter())
+            .chain(d.grammar.iter())
+            .chain(d.tags.iter())
+            .any(|s| s.to_lowercase().contains(&pattern))
+    }
+}
+
+// Words keep the case they were typed in, "Haus" and "haus" are still one word
+pub fn same_word(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+impl Word {
+    // A machine translation or a word as the user typed it, without a part of speech
+    pub fn new(word: &str, lang: Lang) -> Word {
+        Word {
+            word: word.to_string(),
+            lang,
+            pos: None,
+            manual: false,
+        }
+    }
+
+    pub fn same(&self, other: &Word) -> bool {
+        self.lang == other.lang && same_word(&self.word, &other.word)
+    }
+}
+
+// A word removed by the user, kept until the trash is purged
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Deleted {
+    pub translate: Translate,
+    pub deleted: u64,
+    // the language whose delete archived the word, words of one delete are restored together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cascade: Option<Lang>,
+}
+
+impl Deleted {
+    // Only translations into the deleted language, the word itself stayed
+    pub fn stripped(&self) -> bool {
+        self.cascade
+            .as_ref()
+            .is_some_and(|l| l != &self.translate.word.lang)
     }
 }
 
@@ -64,25 +217,95 @@ pub struct User {
     pub translates: Vec<Translate>,
     pub langs: Vec<Lang>,
     pub id: i64,
+    #[serde(default)]
+    pub trash: Vec<Deleted>,
 }
 
 impl User {
     pub fn new(id: i64) -> User {
         User {
-            id: id,
+            id,
             translates: vec![],
             langs: vec![],
+            trash: vec![],
+        }
+    }
+}
+
+pub trait Backend {
+    fn get(&self, user_id: i64) -> Result<Option<User>, Box<dyn error::Error>>;
+
+    // creates the user if it doesn't exist yet. Implementations lock only the updated user,
+    // so the backend is shared without an outer lock
+    fn upsert(
+        &self,
+        user_id: i64,
+        strat: &dyn strategy::UserUpdateStrategy,
+    ) -> Result<(), Box<dyn error::Error>>;
+
+    fn list(&self) -> Result<Vec<User>, Box<dyn error::Error>>;
+
+    // swaps in all users at once, users that aren't in the list are deleted
+    fn replace_all(&self, users: Vec<User>) -> Result<(), Box<dyn error::Error>>;
+}
+
+// Mutations go to append-only journals next to the DB file, one per shard, and are folded
+// into the DB file itself every JOURNAL_COMPACT_SIZE entries and on start
+const JOURNAL_COMPACT_SIZE: usize = 500;
+
+fn journal_path(path: &str, shard: usize) -> String {
+    format!("{}.journal.{}", path, shard)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Durability {
+    // every change is in the journal on disk before upsert returns
+    Sync,
+    // changes are kept in memory and written by flush_loop, a crash loses the last interval
+    Batched,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DurabilityParseError {
+    pub description: String,
+}
+
+impl fmt::Display for DurabilityParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl FromStr for Durability {
+    type Err = DurabilityParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sync" => Ok(Durability::Sync),
+            "batched" => Ok(Durability::Batched),
+            _ => Err(DurabilityParseError {
+                description: format!("Unknown durability mode: {}", s),
+            }),
         }
     }
 }
 
 pub struct Storage {
-    db: Vec<User>,
+    users: shards::Shards,
     path: String,
+    // indexed as shards, so syncs of different shards don't wait for each other
+    journals: Vec<Mutex<journal::Journal>>,
+    // entries in all journals
+    journaled: AtomicUsize,
+    durability: Durability,
+    dirty: AtomicBool,
+    // held from the snapshot until the DB file is written, so an older snapshot
+    // never replaces a newer one and writers don't share the temporary file
+    writing: Mutex<()>,
 }
 
 impl Storage {
-    pub fn new(path: &str) -> Result<Storage, Box<dyn error::Error>> {
+    pub fn new(path: &str, durability: Durability) -> Result<Storage, Box<dyn error::Error>> {
         let raw_json = match fs::read_to_string(path) {
             Ok(raw_json) => raw_json,
             Err(e) => {
@@ -93,65 +316,360 @@ impl Storage {
                 String::from("")
             }
         };
-        if raw_json.trim() == "" {
-            return Ok(Storage {
-                db: vec![],
-                path: path.to_string(),
-            });
+        // older documents are upgraded in memory and written back by compact() below
+        let mut db: HashMap<i64, User> = migration::load(&raw_json)?
+            .into_iter()
+            .map(|u| (u.id, u))
+            .collect();
+        // a user's entries are all in one journal, so the order between journals doesn't matter
+        let mut entries: Vec<journal::Entry> = vec![];
+        // the single journal of older versions
+        let legacy = format!("{}.journal", path);
+        if Path::new(&legacy).exists() {
+            entries.extend(journal::Journal::open(&legacy)?.1);
         }
-        let db: Vec<User> = serde_json::from_str(&raw_json)?;
-
-        Ok(Storage {
-            db: db,
+        let mut journals: Vec<Mutex<journal::Journal>> = vec![];
+        for shard in 0..shards::SHARDS {
+            let (journal, shard_entries) = journal::Journal::open(&journal_path(path, shard))?;
+            journals.push(Mutex::new(journal));
+            entries.extend(shard_entries);
+        }
+        for entry in entries {
+            match entry.user {
+                Some(user) => db.insert(entry.id, user),
+                None => db.remove(&entry.id),
+            };
+        }
+        let stor = Storage {
+            users: shards::Shards::new(db.into_values().collect()),
             path: path.to_string(),
-        })
+            journals,
+            journaled: AtomicUsize::new(0),
+            durability,
+            dirty: AtomicBool::new(false),
+            writing: Mutex::new(()),
+        };
+        // also drops a torn entry at the end of a journal
+        stor.compact()?;
+        if Path::new(&legacy).exists() {
+            fs::remove_file(&legacy)?;
+        }
+
+        Ok(stor)
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn error::Error>> {
-        let b = serde_json::to_string(&self.db.to_vec())?;
-        fs::write(&self.path, b)?;
+    fn compact(&self) -> Result<(), Box<dyn error::Error>> {
+        let _writing = self.writing.lock().unwrap();
+        // shards before the journals, the same order as in commit()
+        let shards = self.users.read_all();
+        let mut journals: Vec<MutexGuard<'_, journal::Journal>> =
+            self.journals.iter().map(|j| j.lock().unwrap()).collect();
+        let b = migration::dump(&shards::users(&shards))?;
+        write_atomic(&self.path, b.as_bytes())?;
+        for journal in journals.iter_mut() {
+            journal.truncate()?;
+        }
+        self.journaled.store(0, Ordering::SeqCst);
+
         Ok(())
     }
 
-    pub fn get(&self, user_id: i64) -> Option<User> {
-        for user in self.db.iter().filter(|u| u.id == user_id) {
-            return Some(user.clone());
+    // Serializes the DB if it was changed since the last snapshot
+    fn snapshot(&self) -> Result<Option<String>, Box<dyn error::Error>> {
+        let shards = self.users.read_all();
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(None);
+        }
+        match migration::dump(&shards::users(&shards)) {
+            Ok(b) => Ok(Some(b)),
+            Err(e) => {
+                self.dirty.store(true, Ordering::SeqCst);
+                Err(e)
+            }
+        }
+    }
+
+    // Called with the write lock of the user shard held.
+    // In sync mode the change is in memory only after it is in the shard journal.
+    // Returns true if the journals should be compacted
+    fn commit(
+        &self,
+        shard: &mut shards::Shard,
+        user_id: i64,
+        user: User,
+    ) -> Result<bool, Box<dyn error::Error>> {
+        let mut compact = false;
+        if self.durability == Durability::Batched {
+            self.dirty.store(true, Ordering::SeqCst);
+        } else {
+            let mut journal = self.journals[shards::index(user_id)].lock().unwrap();
+            journal.append(&journal::Entry {
+                id: user_id,
+                user: Some(user.clone()),
+            })?;
+            compact = self.journaled.fetch_add(1, Ordering::SeqCst) + 1 >= JOURNAL_COMPACT_SIZE;
+        }
+        shard.insert(user_id, user);
+
+        Ok(compact)
+    }
+}
+
+// Writes pending changes of a batched storage. The file is written without holding shard locks,
+// so requests are blocked only while the DB is serialized
+pub fn flush(storage: &Storage) -> Result<(), Box<dyn error::Error>> {
+    let _writing = storage.writing.lock().unwrap();
+    let data = match storage.snapshot()? {
+        Some(data) => data,
+        None => return Ok(()),
+    };
+    if let Err(e) = write_atomic(&storage.path, data.as_bytes()) {
+        storage.dirty.store(true, Ordering::SeqCst);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+// Flushes every interval until stop is signaled or its sender is dropped
+pub fn flush_loop(storage: Arc<Storage>, interval: Duration, stop: mpsc::Receiver<()>) {
+    while let Err(mpsc::RecvTimeoutError::Timeout) = stop.recv_timeout(interval) {
+        if let Err(e) = flush(&storage) {
+            error!("Can't flush DB: {}", e);
         }
+    }
+}
+
+// Writes to a temporary file and renames it over the target, so a crash leaves either
+// the old or the new content but never a truncated file
+pub fn write_atomic(path: &str, data: &[u8]) -> Result<(), Box<dyn error::Error>> {
+    let tmp = format!("{}.tmp", path);
+    {
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(data)?;
+        f.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    let dir = match Path::new(path).parent() {
+        Some(d) if !d.as_os_str().is_empty() => d,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()?;
+
+    Ok(())
+}
 
-        None
+impl Backend for Storage {
+    fn get(&self, user_id: i64) -> Result<Option<User>, Box<dyn error::Error>> {
+        Ok(self.users.get(user_id))
     }
 
-    pub fn upsert(
-        &mut self,
+    fn upsert(
+        &self,
         user_id: i64,
-        strat: impl strategy::UserUpdateStrategy,
+        strat: &dyn strategy::UserUpdateStrategy,
     ) -> Result<(), Box<dyn error::Error>> {
-        let mut pos: i32 = -1;
-        for (i, u) in self.db.iter().enumerate() {
-            if u.id == user_id {
-                pos = i as i32;
-                break;
-            }
+        let compact = {
+            let mut shard = self.users.shard(user_id).write().unwrap();
+            let user = match shard.get(&user_id) {
+                Some(user) => strat.apply(user),
+                None => strat.apply(&User::new(user_id)),
+            };
+            self.commit(&mut shard, user_id, user)?
+        };
+        if compact {
+            self.compact()?;
         }
-        if pos >= 0 {
-            self.db[pos as usize] = strat.apply(&self.db[pos as usize])
-        } else {
-            let user = User::new(user_id);
-            self.db.push(strat.apply(&user))
+
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<User>, Box<dyn error::Error>> {
+        Ok(self.users.list())
+    }
+
+    // The new DB file replaces the old one and the journals before anything is changed in memory
+    fn replace_all(&self, mut users: Vec<User>) -> Result<(), Box<dyn error::Error>> {
+        let _writing = self.writing.lock().unwrap();
+        let mut shards = self.users.write_all();
+        let mut journals: Vec<MutexGuard<'_, journal::Journal>> =
+            self.journals.iter().map(|j| j.lock().unwrap()).collect();
+        users.sort_by_key(|u| u.id);
+        let b = migration::dump(&users)?;
+        write_atomic(&self.path, b.as_bytes())?;
+        for journal in journals.iter_mut() {
+            journal.truncate()?;
         }
+        self.journaled.store(0, Ordering::SeqCst);
+        self.dirty.store(false, Ordering::SeqCst);
+        shards::fill(&mut shards, users);
 
-        self.save()
+        Ok(())
     }
+}
 
-    // unused
-    /*pub fn delete(&mut self, user: User) -> Result<(), Box<dyn error::Error>> {
-        for (i, u) in self.db.iter().enumerate() {
-            if u.id == user.id {
-                self.db.swap_remove(i);
-                break;
-            }
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::sync::{mpsc, Arc};
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::storage::journal::{Entry, Journal};
+    use crate::storage::strategy::AddLang;
+    use crate::storage::{
+        flush, flush_loop, journal_path, migration, shards, Backend, Durability, Storage, User,
+    };
+
+    fn remove_db(path: &str) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_file(format!("{}.journal", path));
+        for shard in 0..shards::SHARDS {
+            let _ = fs::remove_file(journal_path(path, shard));
+        }
+    }
+
+    #[test]
+    fn reopen() {
+        let path = env::temp_dir().join(format!("lengwurds_storage_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        remove_db(path);
+
+        let mut expect = User::new(1);
+        expect.langs.push("en".parse().unwrap());
+        {
+            let stor = Storage::new(path, Durability::Sync).unwrap();
+            stor.upsert(
+                1,
+                &AddLang {
+                    lang: "en".parse().unwrap(),
+                },
+            )
+            .unwrap();
+            // not compacted yet, the change lives only in the journal
+            assert_eq!(
+                migration::dump(&[]).unwrap(),
+                fs::read_to_string(path).unwrap()
+            );
+        }
+        {
+            let stor = Storage::new(path, Durability::Sync).unwrap();
+            assert_eq!(vec![expect.clone()], stor.list().unwrap());
+            assert_eq!("", fs::read_to_string(journal_path(path, 1)).unwrap());
         }
+        let stor = Storage::new(path, Durability::Sync).unwrap();
+        assert_eq!(vec![expect], stor.list().unwrap());
+        stor.replace_all(vec![User::new(2)]).unwrap();
+        let stor = Storage::new(path, Durability::Sync).unwrap();
+        assert_eq!(vec![User::new(2)], stor.list().unwrap());
+
+        remove_db(path);
+    }
+
+    #[test]
+    fn legacy_journal() {
+        let path = env::temp_dir().join(format!("lengwurds_legacy_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        remove_db(path);
+
+        let mut expect = User::new(3);
+        expect.langs.push("en".parse().unwrap());
+        let legacy = format!("{}.journal", path);
+        Journal::open(&legacy)
+            .unwrap()
+            .0
+            .append(&Entry {
+                id: 3,
+                user: Some(expect.clone()),
+            })
+            .unwrap();
+        let stor = Storage::new(path, Durability::Sync).unwrap();
+        assert_eq!(vec![expect], stor.list().unwrap());
+        assert!(fs::metadata(&legacy).is_err());
 
-        self.save()
-    }*/
+        remove_db(path);
+    }
+
+    #[test]
+    fn batched() {
+        let path = env::temp_dir().join(format!("lengwurds_batched_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        remove_db(path);
+
+        let stor = Storage::new(path, Durability::Batched).unwrap();
+        let empty = fs::read_to_string(path).unwrap();
+        stor.upsert(
+            1,
+            &AddLang {
+                lang: "en".parse().unwrap(),
+            },
+        )
+        .unwrap();
+        assert_eq!(empty, fs::read_to_string(path).unwrap());
+        assert_eq!("", fs::read_to_string(journal_path(path, 1)).unwrap());
+
+        flush(&stor).unwrap();
+        let mut expect = User::new(1);
+        expect.langs.push("en".parse().unwrap());
+        assert_eq!(
+            migration::dump(&[expect.clone()]).unwrap(),
+            fs::read_to_string(path).unwrap()
+        );
+        // nothing changed, nothing to write
+        fs::remove_file(path).unwrap();
+        flush(&stor).unwrap();
+        assert!(fs::read_to_string(path).is_err());
+
+        remove_db(path);
+    }
+
+    #[test]
+    fn concurrent_flush() {
+        let path = env::temp_dir().join(format!("lengwurds_flush_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        remove_db(path);
+
+        let stor = Arc::new(Storage::new(path, Durability::Batched).unwrap());
+        let (stop, stopped) = mpsc::channel();
+        let loop_stor = stor.clone();
+        let flusher =
+            thread::spawn(move || flush_loop(loop_stor, Duration::from_millis(1), stopped));
+        let writers: Vec<thread::JoinHandle<()>> = (0..4)
+            .map(|t| {
+                let stor = stor.clone();
+                thread::spawn(move || {
+                    for id in 0..25 {
+                        stor.upsert(
+                            t * 25 + id,
+                            &AddLang {
+                                lang: "en".parse().unwrap(),
+                            },
+                        )
+                        .unwrap();
+                        flush(&stor).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for w in writers {
+            w.join().unwrap();
+        }
+        drop(stop);
+        flusher.join().unwrap();
+        flush(&stor).unwrap();
+
+        let reopened = Storage::new(path, Durability::Batched).unwrap();
+        assert_eq!(100, reopened.list().unwrap().len());
+        assert_eq!(stor.list().unwrap(), reopened.list().unwrap());
+
+        remove_db(path);
+    }
+
+    #[test]
+    fn parse_durability() {
+        assert_eq!(Ok(Durability::Sync), "sync".parse());
+        assert_eq!(Ok(Durability::Batched), " Batched".parse());
+        assert!("async".parse::<Durability>().is_err());
+    }
 }
@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+pub const DAY: u64 = 24 * 60 * 60;
+const DEFAULT_EASE: f64 = 2.5;
+const MIN_EASE: f64 = 1.3;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    // SM-2 response quality, 0..=5. Anything below 3 means the word was forgotten
+    fn quality(&self) -> u8 {
+        match self {
+            Grade::Again => 1,
+            Grade::Hard => 3,
+            Grade::Good => 4,
+            Grade::Easy => 5,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GradeParseError {
+    pub description: String,
+}
+
+impl fmt::Display for GradeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl FromStr for Grade {
+    type Err = GradeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "again" | "1" => Ok(Grade::Again),
+            "hard" | "2" => Ok(Grade::Hard),
+            "good" | "3" => Ok(Grade::Good),
+            "easy" | "4" => Ok(Grade::Easy),
+            _ => Err(GradeParseError {
+                description: "Unknown grade".to_string(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Schedule {
+    pub ease: f64,
+    // days
+    pub interval: u32,
+    pub repetitions: u32,
+    // unix timestamp, new words are due immediately
+    pub due: u64,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            ease: DEFAULT_EASE,
+            interval: 0,
+            repetitions: 0,
+            due: 0,
+        }
+    }
+}
+
+impl Schedule {
+    pub fn is_due(&self, now: u64) -> bool {
+        self.due <= now
+    }
+
+    pub fn review(&self, grade: Grade, now: u64) -> Schedule {
+        let q = grade.quality() as f64;
+        let mut s = self.clone();
+        // a failed answer starts the repetitions again and keeps the ease
+        if grade.quality() < 3 {
+            s.repetitions = 0;
+            s.interval = 1;
+        } else {
+            s.interval = match s.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (s.interval as f64 * s.ease).round() as u32,
+            };
+            s.repetitions += 1;
+            s.ease += 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02);
+            if s.ease < MIN_EASE {
+                s.ease = MIN_EASE;
+            }
+        }
+        s.due = now + s.interval as u64 * DAY;
+
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::schedule::{Grade, GradeParseError, Schedule, DAY, MIN_EASE};
+
+    #[test]
+    fn parse_grade() {
+        assert_eq!(Ok(Grade::Again), "again".parse());
+        assert_eq!(Ok(Grade::Hard), "Hard".parse());
+        assert_eq!(Ok(Grade::Good), "3".parse());
+        assert_eq!(Ok(Grade::Easy), " easy ".parse());
+        assert_eq!(
+            Err(GradeParseError {
+                description: "Unknown grade".to_string()
+            }),
+            "perfect".parse::<Grade>()
+        );
+    }
+
+    #[test]
+    fn review() {
+        let now: u64 = 1000;
+        let s = Schedule::default();
+        assert!(s.is_due(now));
+
+        let s = s.review(Grade::Good, now);
+        assert_eq!(1, s.interval);
+        assert_eq!(1, s.repetitions);
+        assert_eq!(now + DAY, s.due);
+        assert!(!s.is_due(now));
+
+        let s = s.review(Grade::Good, now);
+        assert_eq!(6, s.interval);
+        assert_eq!(2, s.repetitions);
+
+        let s = s.review(Grade::Easy, now);
+        assert_eq!(15, s.interval);
+        assert_eq!(3, s.repetitions);
+        assert!((s.ease - 2.6).abs() < 1e-9, "Ease: {}", s.ease);
+        assert_eq!(now + 15 * DAY, s.due);
+
+        let s = s.review(Grade::Again, now);
+        assert_eq!(1, s.interval);
+        assert_eq!(0, s.repetitions);
+        assert!((s.ease - 2.6).abs() < 1e-9, "Ease: {}", s.ease);
+        assert_eq!(now + DAY, s.due);
+    }
+
+    #[test]
+    fn review_min_ease() {
+        let mut s = Schedule::default();
+        for _ in 0..10 {
+            s = s.review(Grade::Hard, 0);
+        }
+        assert_eq!(MIN_EASE, s.ease);
+        assert_eq!(MIN_EASE, s.review(Grade::Again, 0).ease);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::storage::User;
+
+pub const SHARDS: usize = 16;
+
+pub type Shard = HashMap<i64, User>;
+
+// Users indexed by id and split between independently locked shards,
+// so requests of different users don't wait for each other
+pub struct Shards {
+    shards: Vec<RwLock<Shard>>,
+}
+
+impl Shards {
+    pub fn new(users: Vec<User>) -> Shards {
+        let shards: Vec<RwLock<Shard>> = (0..SHARDS).map(|_| RwLock::new(HashMap::new())).collect();
+        for user in users {
+            shards[index(user.id)]
+                .write()
+                .unwrap()
+                .insert(user.id, user);
+        }
+
+        Shards { shards }
+    }
+
+    pub fn shard(&self, user_id: i64) -> &RwLock<Shard> {
+        &self.shards[index(user_id)]
+    }
+
+    pub fn get(&self, user_id: i64) -> Option<User> {
+        self.shard(user_id).read().unwrap().get(&user_id).cloned()
+    }
+
+    // Blocks writes to every shard until the guards are dropped.
+    // Locks are always taken in the same order
+    pub fn read_all(&self) -> Vec<RwLockReadGuard<'_, Shard>> {
+        self.shards.iter().map(|s| s.read().unwrap()).collect()
+    }
+
+    // Blocks every shard like read_all, but for a change of all of them
+    pub fn write_all(&self) -> Vec<RwLockWriteGuard<'_, Shard>> {
+        self.shards.iter().map(|s| s.write().unwrap()).collect()
+    }
+
+    pub fn list(&self) -> Vec<User> {
+        users(&self.read_all())
+    }
+}
+
+// Users from all shards ordered by id
+pub fn users(shards: &[RwLockReadGuard<'_, Shard>]) -> Vec<User> {
+    let mut users: Vec<User> = shards.iter().flat_map(|s| s.values().cloned()).collect();
+    users.sort_by_key(|u| u.id);
+
+    users
+}
+
+// Replaces the content of all shards, the guards must come from write_all
+pub fn fill(shards: &mut [RwLockWriteGuard<'_, Shard>], users: Vec<User>) {
+    for shard in shards.iter_mut() {
+        shard.clear();
+    }
+    for user in users {
+        shards[index(user.id)].insert(user.id, user);
+    }
+}
+
+pub fn index(user_id: i64) -> usize {
+    user_id.rem_euclid(SHARDS as i64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::shards::Shards;
+    use crate::storage::User;
+
+    #[test]
+    fn index() {
+        let ids: Vec<i64> = vec![-100123, 3, 1, 17, 0, -1];
+        let shards = Shards::new(ids.iter().map(|id| User::new(*id)).collect());
+        for id in ids.iter() {
+            assert_eq!(Some(User::new(*id)), shards.get(*id));
+        }
+        assert_eq!(None, shards.get(2));
+        assert_eq!(
+            vec![-100123, -1, 0, 1, 3, 17],
+            shards.list().iter().map(|u| u.id).collect::<Vec<i64>>()
+        );
+    }
+}
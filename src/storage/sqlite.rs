@@ -0,0 +1,319 @@
+use std::error;
+use std::fs;
+use std::sync::Mutex;
+
+use crate::storage::{migration, strategy, Backend, Deleted, Translate, User};
+use crate::translate::Lang;
+
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    langs TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS translates (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    pos INTEGER NOT NULL,
+    word TEXT NOT NULL,
+    lang TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (user_id, pos)
+);
+CREATE INDEX IF NOT EXISTS translates_word ON translates(user_id, word);
+CREATE TABLE IF NOT EXISTS trash (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    pos INTEGER NOT NULL,
+    deleted INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    cascade TEXT,
+    PRIMARY KEY (user_id, pos)
+);
+";
+
+// Every user is a row in `users` and every word is a row in `translates`.
+// Translates are stored as JSON in `data`, so new fields don't need a schema change
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn new(path: &str) -> Result<SqliteStorage, Box<dyn error::Error>> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+        conn.execute_batch(SCHEMA)?;
+        // DBs created before language deletes were archived
+        let has_cascade: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('trash') WHERE name = 'cascade'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_cascade {
+            conn.execute("ALTER TABLE trash ADD COLUMN cascade TEXT", [])?;
+        }
+
+        Ok(SqliteStorage {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    // One-shot migration from the JSON file storage. Users already in the DB are overwritten
+    pub fn import_json(&self, path: &str) -> Result<usize, Box<dyn error::Error>> {
+        let users: Vec<User> = migration::load(&fs::read_to_string(path)?)?;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for user in users.iter() {
+            save_user(&tx, user)?;
+        }
+        tx.commit()?;
+
+        Ok(users.len())
+    }
+}
+
+fn load_user(tx: &Transaction, user_id: i64) -> Result<Option<User>, Box<dyn error::Error>> {
+    let langs: Option<String> = tx
+        .query_row(
+            "SELECT langs FROM users WHERE id = ?1",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let langs: Vec<Lang> = match langs {
+        Some(langs) => serde_json::from_str(&langs)?,
+        None => return Ok(None),
+    };
+    let mut stmt = tx.prepare("SELECT data FROM translates WHERE user_id = ?1 ORDER BY pos")?;
+    let rows = stmt.query_map(params![user_id], |row| row.get::<_, String>(0))?;
+    let mut translates: Vec<Translate> = vec![];
+    for data in rows {
+        translates.push(serde_json::from_str(&data?)?);
+    }
+    let mut stmt =
+        tx.prepare("SELECT deleted, data, cascade FROM trash WHERE user_id = ?1 ORDER BY pos")?;
+    let rows = stmt.query_map(params![user_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        ))
+    })?;
+    let mut trash: Vec<Deleted> = vec![];
+    for row in rows {
+        let (deleted, data, cascade) = row?;
+        trash.push(Deleted {
+            translate: serde_json::from_str(&data)?,
+            deleted: deleted as u64,
+            cascade: cascade.map(|lang| Lang { lang }),
+        });
+    }
+
+    Ok(Some(User {
+        id: user_id,
+        translates,
+        langs,
+        trash,
+    }))
+}
+
+fn save_user(tx: &Transaction, user: &User) -> Result<(), Box<dyn error::Error>> {
+    tx.execute(
+        "INSERT INTO users (id, langs) VALUES (?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET langs = excluded.langs",
+        params![user.id, serde_json::to_string(&user.langs)?],
+    )?;
+    tx.execute(
+        "DELETE FROM translates WHERE user_id = ?1",
+        params![user.id],
+    )?;
+    let mut stmt = tx.prepare(
+        "INSERT INTO translates (user_id, pos, word, lang, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (pos, tr) in user.translates.iter().enumerate() {
+        stmt.execute(params![
+            user.id,
+            pos as i64,
+            tr.word.word,
+            tr.word.lang.lang,
+            serde_json::to_string(tr)?
+        ])?;
+    }
+    tx.execute("DELETE FROM trash WHERE user_id = ?1", params![user.id])?;
+    let mut stmt = tx.prepare(
+        "INSERT INTO trash (user_id, pos, deleted, data, cascade) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (pos, d) in user.trash.iter().enumerate() {
+        stmt.execute(params![
+            user.id,
+            pos as i64,
+            d.deleted as i64,
+            serde_json::to_string(&d.translate)?,
+            d.cascade.as_ref().map(|l| l.lang.clone())
+        ])?;
+    }
+
+    Ok(())
+}
+
+impl Backend for SqliteStorage {
+    fn get(&self, user_id: i64) -> Result<Option<User>, Box<dyn error::Error>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        load_user(&tx, user_id)
+    }
+
+    fn upsert(
+        &self,
+        user_id: i64,
+        strat: &dyn strategy::UserUpdateStrategy,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let user = match load_user(&tx, user_id)? {
+            Some(user) => user,
+            None => User::new(user_id),
+        };
+        save_user(&tx, &strat.apply(&user))?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<User>, Box<dyn error::Error>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let ids: Vec<i64> = {
+            let mut stmt = tx.prepare("SELECT id FROM users ORDER BY id")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        let mut users: Vec<User> = vec![];
+        for id in ids {
+            if let Some(user) = load_user(&tx, id)? {
+                users.push(user)
+            }
+        }
+
+        Ok(users)
+    }
+
+    // One transaction, words and trash go away with their users
+    fn replace_all(&self, users: Vec<User>) -> Result<(), Box<dyn error::Error>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM users", [])?;
+        for user in users.iter() {
+            save_user(&tx, user)?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use crate::storage::sqlite::SqliteStorage;
+    use crate::storage::strategy::{
+        AddLang, AddTranslate, Cascade, DeleteLang, DeleteWord, UserUpdateStrategy,
+    };
+    use crate::storage::{Backend, Deleted, Translate, User, Word};
+
+    fn translate(word: &str) -> Translate {
+        Translate::new(
+            Word::new(word, "en".parse().unwrap()),
+            vec![Word::new(&format!("{} ru", word), "ru".parse().unwrap())],
+        )
+    }
+
+    #[test]
+    fn upsert() {
+        let stor = SqliteStorage::new(":memory:").unwrap();
+        let id = 1;
+        assert_eq!(None, stor.get(id).unwrap());
+
+        stor.upsert(
+            id,
+            &AddLang {
+                lang: "en".parse().unwrap(),
+            },
+        )
+        .unwrap();
+        stor.upsert(
+            id,
+            &AddTranslate {
+                tran: translate("word"),
+            },
+        )
+        .unwrap();
+        stor.upsert(
+            id,
+            &AddTranslate {
+                tran: translate("door"),
+            },
+        )
+        .unwrap();
+
+        let mut expect = User::new(id);
+        expect.langs.push("en".parse().unwrap());
+        expect.translates.push(translate("word"));
+        expect.translates.push(translate("door"));
+        assert_eq!(Some(expect.clone()), stor.get(id).unwrap());
+        assert_eq!(None, stor.get(2).unwrap());
+
+        stor.upsert(
+            id,
+            &DeleteWord {
+                word: "word".to_string(),
+                lang: None,
+                now: 10,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        expect.trash.push(Deleted {
+            translate: expect.translates.remove(0),
+            deleted: 10,
+            cascade: None,
+        });
+        assert_eq!(Some(expect.clone()), stor.get(id).unwrap());
+
+        let archive = DeleteLang {
+            lang: "ru".parse().unwrap(),
+            cascade: Some(Cascade::Archive),
+            now: 20,
+        };
+        stor.upsert(id, &archive).unwrap();
+        let archived = archive.apply(&expect);
+        assert_eq!(Some(archived), stor.get(id).unwrap());
+
+        stor.upsert(
+            2,
+            &AddLang {
+                lang: "ru".parse().unwrap(),
+            },
+        )
+        .unwrap();
+        assert_eq!(2, stor.list().unwrap().len());
+        stor.replace_all(vec![expect.clone()]).unwrap();
+        assert_eq!(vec![expect], stor.list().unwrap());
+    }
+
+    #[test]
+    fn import_json() {
+        let mut user = User::new(1);
+        user.langs.push("en".parse().unwrap());
+        user.translates.push(translate("word"));
+        let path = env::temp_dir().join("lengwurds_sqlite_import_test.json");
+        fs::write(&path, serde_json::to_string(&vec![user.clone()]).unwrap()).unwrap();
+
+        let stor = SqliteStorage::new(":memory:").unwrap();
+        assert_eq!(1, stor.import_json(path.to_str().unwrap()).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Some(user), stor.get(1).unwrap());
+    }
+}
@@ -1,14 +1,23 @@
+use std::cell::{Cell, RefCell};
 use std::collections::HashSet;
 
-use crate::storage::{Translate, User, Word};
+use crate::storage::schedule::Grade;
+use crate::storage::{same_word, tag, Deleted, Details, Translate, User, Word};
 use crate::translate::Lang;
 
 pub trait UserUpdateStrategy {
     fn apply(&self, user: &User) -> User;
 }
 
+// Moves the word to the trash, so it can be restored with RestoreWord.
+// Without a language the word is deleted in all languages
+#[derive(Default)]
 pub struct DeleteWord {
     pub word: String,
+    pub lang: Option<Lang>,
+    pub now: u64,
+    // set by apply to the number of deleted entries
+    pub deleted: Cell<usize>,
 }
 
 impl UserUpdateStrategy for DeleteWord {
@@ -16,17 +25,113 @@ impl UserUpdateStrategy for DeleteWord {
         let mut u = user.clone();
         let mut saved: Vec<Translate> = vec![];
         for tr in user.translates.iter() {
-            if tr.word.word == self.word {
+            let lang_match = self.lang.as_ref().is_none_or(|l| l == &tr.word.lang);
+            if same_word(&tr.word.word, &self.word) && lang_match {
+                u.trash.push(Deleted {
+                    translate: tr.clone(),
+                    deleted: self.now,
+                    cascade: None,
+                });
                 continue;
             }
             saved.push(tr.clone())
         }
+        self.deleted.set(user.translates.len() - saved.len());
         u.translates = saved;
 
         u
     }
 }
 
+// Moves words back from the trash: the given word or, if None, the word deleted last,
+// or all words of the language deleted last with their translations and the language itself.
+// A restored word replaces the same word added again after the delete, keeping its translates
+#[derive(Default)]
+pub struct RestoreWord {
+    pub word: Option<String>,
+    // set by apply to the words taken from the trash, so the caller gets them from the same upsert
+    pub restored: RefCell<Vec<Translate>>,
+}
+
+impl UserUpdateStrategy for RestoreWord {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        // the trash is in the order of deletes, one delete moves all langs of the word
+        let last = user.trash.last();
+        let (restored, kept): (Vec<Deleted>, Vec<Deleted>) =
+            user.trash
+                .iter()
+                .cloned()
+                .partition(|d| match (&self.word, last) {
+                    (Some(w), _) => same_word(&d.translate.word.word, w),
+                    (None, Some(l)) => {
+                        d.deleted == l.deleted
+                            && match &l.cascade {
+                                Some(lang) => d.cascade.as_ref() == Some(lang),
+                                None => {
+                                    d.cascade.is_none()
+                                        && same_word(&d.translate.word.word, &l.translate.word.word)
+                                }
+                            }
+                    }
+                    (None, None) => false,
+                });
+        u.trash = kept;
+        *self.restored.borrow_mut() = restored.iter().map(|d| d.translate.clone()).collect();
+        for lang in restored.iter().filter_map(|d| d.cascade.as_ref()) {
+            if !u.langs.contains(lang) {
+                u.langs.push(lang.clone())
+            }
+        }
+        let mut done: Vec<Word> = vec![];
+        // the latest delete wins if the word was deleted several times
+        for d in restored.into_iter().rev() {
+            let stripped = d.stripped();
+            let mut tr = d.translate;
+            if stripped {
+                if let Some(current) = u.translates.iter_mut().find(|t| t.word.same(&tr.word)) {
+                    for t in tr.translates {
+                        if !current.translates.contains(&t) {
+                            current.translates.push(t)
+                        }
+                    }
+                    continue;
+                }
+                // the word was deleted after its language, the translations come back alone
+            }
+            if done.iter().any(|w| w.same(&tr.word)) {
+                continue;
+            }
+            done.push(tr.word.clone());
+            if let Some(i) = u.translates.iter().position(|t| t.word.same(&tr.word)) {
+                let current = u.translates.remove(i);
+                for t in current.translates {
+                    if !tr.translates.contains(&t) {
+                        tr.translates.push(t)
+                    }
+                }
+            }
+            u.translates.push(tr)
+        }
+
+        u
+    }
+}
+
+// Drops words deleted before the given time
+pub struct PurgeTrash {
+    pub before: u64,
+}
+
+impl UserUpdateStrategy for PurgeTrash {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        u.trash.retain(|d| d.deleted >= self.before);
+
+        u
+    }
+}
+
 pub struct AddTranslate {
     pub tran: Translate,
 }
@@ -40,14 +145,17 @@ impl UserUpdateStrategy for AddTranslate {
                 found = true;
                 break;
             }
-            if tr.word == self.tran.word {
+            if tr.word.same(&self.tran.word) {
                 found = true;
-                let mut hset: HashSet<Word> = HashSet::new();
-                for t in &tr.translates {
-                    hset.insert(t.clone());
-                }
+                // the spelling typed last wins, e.g. "Haus" over "haus"
+                u.translates[i].word.word = self.tran.word.word.clone();
+                // a known translation stays as it is, manual ones keep their flag,
+                // a rejected one isn't added again
                 for t in &self.tran.translates {
-                    if hset.contains(t) {
+                    let current = &u.translates[i];
+                    if current.translates.iter().any(|e| e.same(t))
+                        || current.rejected.iter().any(|e| e.same(t))
+                    {
                         continue;
                     }
                     u.translates[i].translates.push(t.clone())
@@ -63,6 +171,182 @@ impl UserUpdateStrategy for AddTranslate {
     }
 }
 
+// Adds many words with one storage update, merged like AddTranslate
+pub struct AddTranslates {
+    pub trans: Vec<Translate>,
+}
+
+impl UserUpdateStrategy for AddTranslates {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        for tran in self.trans.iter() {
+            u = AddTranslate { tran: tran.clone() }.apply(&u);
+        }
+
+        u
+    }
+}
+
+// A translation typed by the user for every entry of the word,
+// it takes the place of the same machine translation and isn't rejected anymore
+pub struct AddManualTranslate {
+    pub word: String,
+    pub tran: Word,
+}
+
+impl UserUpdateStrategy for AddManualTranslate {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        let mut tran = self.tran.clone();
+        tran.manual = true;
+        for tr in u
+            .translates
+            .iter_mut()
+            .filter(|t| same_word(&t.word.word, &self.word))
+        {
+            tr.rejected.retain(|t| !t.same(&tran));
+            match tr.translates.iter().position(|t| t.same(&tran)) {
+                Some(i) => tr.translates[i] = tran.clone(),
+                None => tr.translates.push(tran.clone()),
+            }
+        }
+
+        u
+    }
+}
+
+// Removes a wrong translation of the word, in one language if it is given.
+// The entry remembers it, so translating the word again doesn't restore it
+pub struct DeleteTranslate {
+    pub word: String,
+    pub tran: String,
+    pub lang: Option<Lang>,
+}
+
+impl UserUpdateStrategy for DeleteTranslate {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        for tr in u
+            .translates
+            .iter_mut()
+            .filter(|t| same_word(&t.word.word, &self.word))
+        {
+            let lang_match = |t: &Word| self.lang.as_ref().is_none_or(|l| l == &t.lang);
+            let (removed, kept): (Vec<Word>, Vec<Word>) = tr
+                .translates
+                .drain(..)
+                .partition(|t| same_word(&t.word, &self.tran) && lang_match(t));
+            tr.translates = kept;
+            for t in removed {
+                if !tr.rejected.iter().any(|r| r.same(&t)) {
+                    tr.rejected.push(t)
+                }
+            }
+        }
+
+        u
+    }
+}
+
+// The given translations go first in the given order, the rest keep theirs after them
+pub struct ReorderTranslates {
+    pub word: String,
+    pub order: Vec<String>,
+}
+
+impl UserUpdateStrategy for ReorderTranslates {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        for tr in u
+            .translates
+            .iter_mut()
+            .filter(|t| same_word(&t.word.word, &self.word))
+        {
+            let mut rest = tr.translates.clone();
+            let mut ordered: Vec<Word> = vec![];
+            for o in self.order.iter() {
+                while let Some(i) = rest.iter().position(|t| same_word(&t.word, o)) {
+                    ordered.push(rest.remove(i))
+                }
+            }
+            ordered.extend(rest);
+            tr.translates = ordered;
+        }
+
+        u
+    }
+}
+
+// A change of the user's details of a word
+#[derive(Clone, PartialEq, Debug)]
+pub enum Edit {
+    // None removes the note
+    Note(Option<String>),
+    AddExample(String),
+    // number of the example as it is shown, from 1
+    DeleteExample(usize),
+    Grammar(Option<String>),
+    AddTags(Vec<String>),
+    DeleteTags(Vec<String>),
+    // all details at once
+    Replace(Details),
+}
+
+// Edits details of every entry of the word
+pub struct EditDetails {
+    pub word: String,
+    pub edit: Edit,
+}
+
+impl UserUpdateStrategy for EditDetails {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        for tr in u
+            .translates
+            .iter_mut()
+            .filter(|t| same_word(&t.word.word, &self.word))
+        {
+            let d = &mut tr.details;
+            match &self.edit {
+                Edit::Note(note) => d.note = note.clone(),
+                Edit::AddExample(e) => {
+                    if !d.examples.contains(e) {
+                        d.examples.push(e.clone())
+                    }
+                }
+                Edit::DeleteExample(n) => {
+                    if *n > 0 && *n <= d.examples.len() {
+                        d.examples.remove(n - 1);
+                    }
+                }
+                Edit::Grammar(grammar) => d.grammar = grammar.clone(),
+                Edit::AddTags(tags) => add_tags(d, tags),
+                Edit::DeleteTags(tags) => {
+                    let tags: Vec<String> = tags.iter().map(|t| tag(t)).collect();
+                    d.tags.retain(|t| !tags.contains(t))
+                }
+                Edit::Replace(details) => {
+                    *d = Details {
+                        tags: vec![],
+                        ..details.clone()
+                    };
+                    add_tags(d, &details.tags)
+                }
+            }
+        }
+
+        u
+    }
+}
+
+fn add_tags(d: &mut Details, tags: &[String]) {
+    for t in tags.iter().map(|t| tag(t)) {
+        if !t.is_empty() && !d.tags.contains(&t) {
+            d.tags.push(t)
+        }
+    }
+}
+
 pub struct AddLang {
     pub lang: Lang,
 }
@@ -70,7 +354,7 @@ pub struct AddLang {
 impl UserUpdateStrategy for AddLang {
     fn apply(&self, user: &User) -> User {
         let mut u = user.clone();
-        for _ in user.langs.iter().filter(|l| l.lang == self.lang.lang) {
+        if user.langs.iter().any(|l| l.lang == self.lang.lang) {
             return u;
         }
         u.langs.push(self.lang.clone());
@@ -79,9 +363,21 @@ impl UserUpdateStrategy for AddLang {
     }
 }
 
-// TODO: may be need a strategy to delete all words on this language
+// What happens to words of a deleted language
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cascade {
+    Delete,
+    // to the trash, so they can be restored with RestoreWord
+    Archive,
+}
+
+// Without cascade only the language is removed and the words stay as they are.
+// With cascade words in the language are removed and translations into it are stripped,
+// an archive keeps the stripped translations in the trash too
 pub struct DeleteLang {
     pub lang: Lang,
+    pub cascade: Option<Cascade>,
+    pub now: u64,
 }
 
 impl UserUpdateStrategy for DeleteLang {
@@ -91,8 +387,38 @@ impl UserUpdateStrategy for DeleteLang {
             .langs
             .iter()
             .filter(|l| l.lang != self.lang.lang)
-            .map(|l| l.clone())
+            .cloned()
             .collect();
+        let cascade = match self.cascade {
+            Some(c) => c,
+            None => return u,
+        };
+        let mut saved: Vec<Translate> = vec![];
+        for tr in user.translates.iter() {
+            if tr.word.lang == self.lang {
+                if cascade == Cascade::Archive {
+                    u.trash.push(Deleted {
+                        translate: tr.clone(),
+                        deleted: self.now,
+                        cascade: Some(self.lang.clone()),
+                    });
+                }
+                continue;
+            }
+            let mut tr = tr.clone();
+            let (stripped, kept): (Vec<Word>, Vec<Word>) =
+                tr.translates.into_iter().partition(|w| w.lang == self.lang);
+            if cascade == Cascade::Archive && !stripped.is_empty() {
+                u.trash.push(Deleted {
+                    translate: Translate::new(tr.word.clone(), stripped),
+                    deleted: self.now,
+                    cascade: Some(self.lang.clone()),
+                });
+            }
+            tr.translates = kept;
+            saved.push(tr)
+        }
+        u.translates = saved;
 
         u
     }
@@ -121,12 +447,38 @@ impl UserUpdateStrategy for UpdateLastSeen {
     }
 }
 
+pub struct Review {
+    pub word: String,
+    // None grades the word in every language
+    pub lang: Option<Lang>,
+    pub grade: Grade,
+    pub now: u64,
+}
+
+impl UserUpdateStrategy for Review {
+    fn apply(&self, user: &User) -> User {
+        let mut u = user.clone();
+        for tr in u.translates.iter_mut() {
+            let lang_match = self.lang.as_ref().is_none_or(|l| l == &tr.word.lang);
+            if !same_word(&tr.word.word, &self.word) || !lang_match {
+                continue;
+            }
+            tr.schedule = tr.schedule.review(self.grade, self.now);
+        }
+
+        u
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::storage::schedule::{Grade, Schedule};
     use crate::storage::strategy::{
-        AddLang, AddTranslate, DeleteLang, DeleteWord, UpdateLastSeen, UserUpdateStrategy,
+        AddLang, AddManualTranslate, AddTranslate, AddTranslates, Cascade, DeleteLang,
+        DeleteTranslate, DeleteWord, Edit, EditDetails, PurgeTrash, ReorderTranslates, RestoreWord,
+        Review, UpdateLastSeen, UserUpdateStrategy,
     };
-    use crate::storage::{Translate, User, Word};
+    use crate::storage::{Deleted, Details, Translate, User, Word};
 
     #[test]
     fn add_lang() {
@@ -172,6 +524,8 @@ mod tests {
     fn del_lang() {
         let strat = DeleteLang {
             lang: "en".parse().unwrap(),
+            cascade: None,
+            now: 0,
         };
         let mut test_u: Vec<User> = vec![];
         let mut expect_u: Vec<User> = vec![];
@@ -210,6 +564,9 @@ mod tests {
     fn del_word() {
         let strat = DeleteWord {
             word: "word".to_string(),
+            lang: None,
+            now: 10,
+            ..Default::default()
         };
         let mut test_u: Vec<User> = vec![];
         let mut expect_u: Vec<User> = vec![];
@@ -227,16 +584,27 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
+            });
+            let mut u_expect = User::new(id);
+            u_expect.trash.push(Deleted {
+                translate: u_test.translates[0].clone(),
+                deleted: 10,
+                cascade: None,
             });
             test_u.push(u_test);
-
-            let u_expect = User::new(id);
             expect_u.push(u_expect)
         }
         {
@@ -245,37 +613,62 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             u_test.translates.push(Translate {
                 word: Word {
                     word: "door".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "дверь".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
-            test_u.push(u_test);
-
             let mut u_expect = User::new(id);
+            u_expect.trash.push(Deleted {
+                translate: u_test.translates[0].clone(),
+                deleted: 10,
+                cascade: None,
+            });
+            test_u.push(u_test);
             u_expect.translates.push(Translate {
                 word: Word {
                     word: "door".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "дверь".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             expect_u.push(u_expect)
         }
@@ -292,12 +685,19 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             },
         };
         let mut test_u: Vec<User> = vec![];
@@ -312,12 +712,19 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             expect_u.push(u_expect)
         }
@@ -327,12 +734,19 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             test_u.push(u_test);
 
@@ -341,12 +755,19 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             expect_u.push(u_expect)
         }
@@ -356,12 +777,19 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![Word {
                     word: "сөз".to_string(),
                     lang: "kk".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 }],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             test_u.push(u_test);
 
@@ -370,18 +798,27 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![
                     Word {
                         word: "сөз".to_string(),
                         lang: "kk".parse().unwrap(),
+                        pos: None,
+                        manual: false,
                     },
                     Word {
                         word: "слово".to_string(),
                         lang: "ru".parse().unwrap(),
+                        pos: None,
+                        manual: false,
                     },
                 ],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             expect_u.push(u_expect)
         }
@@ -399,13 +836,17 @@ mod tests {
                 Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
             ],
-            last_seen: last_seen,
+            last_seen,
         };
         let mut test_u: Vec<User> = vec![];
         let mut expect_u: Vec<User> = vec![];
@@ -416,9 +857,14 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             test_u.push(u_test);
 
@@ -427,9 +873,14 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
-                last_seen: last_seen,
+                last_seen,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
 
             expect_u.push(u_expect)
@@ -440,17 +891,27 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             u_test.translates.push(Translate {
                 word: Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             test_u.push(u_test);
 
@@ -459,17 +920,27 @@ mod tests {
                 word: Word {
                     word: "word".to_string(),
                     lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
-                last_seen: last_seen,
+                last_seen,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             u_expect.translates.push(Translate {
                 word: Word {
                     word: "слово".to_string(),
                     lang: "ru".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
-                last_seen: last_seen,
+                last_seen,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
 
             expect_u.push(u_expect)
@@ -480,9 +951,14 @@ mod tests {
                 word: Word {
                     word: "сөз".to_string(),
                     lang: "kk".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             test_u.push(u_test);
 
@@ -491,9 +967,14 @@ mod tests {
                 word: Word {
                     word: "сөз".to_string(),
                     lang: "kk".parse().unwrap(),
+                    pos: None,
+                    manual: false,
                 },
                 translates: vec![],
                 last_seen: 0,
+                schedule: Schedule::default(),
+                details: Details::default(),
+                rejected: vec![],
             });
             expect_u.push(u_expect)
         }
@@ -502,4 +983,368 @@ mod tests {
             assert_eq!(expect_u[i], strat.apply(tst), "Failed test: {}", i)
         }
     }
+
+    #[test]
+    fn review() {
+        let now: u64 = 10;
+        let mut strat = Review {
+            word: "word".to_string(),
+            lang: Some("en".parse().unwrap()),
+            grade: Grade::Good,
+            now,
+        };
+        let id = 1;
+        let mut u_test = User::new(id);
+        u_test.translates.push(Translate::new(
+            Word::new("word", "en".parse().unwrap()),
+            vec![],
+        ));
+        u_test.translates.push(Translate::new(
+            Word::new("door", "en".parse().unwrap()),
+            vec![],
+        ));
+        u_test.translates.push(Translate::new(
+            Word::new("Word", "de".parse().unwrap()),
+            vec![],
+        ));
+
+        let mut u_expect = u_test.clone();
+        u_expect.translates[0].schedule = Schedule::default().review(Grade::Good, now);
+        assert_eq!(u_expect, strat.apply(&u_test));
+
+        strat.lang = None;
+        u_expect.translates[2].schedule = Schedule::default().review(Grade::Good, now);
+        assert_eq!(u_expect, strat.apply(&u_test))
+    }
+
+    fn translate(word: &str, lang: &str, tr: &str) -> Translate {
+        Translate::new(
+            Word::new(word, lang.parse().unwrap()),
+            vec![Word::new(tr, "ru".parse().unwrap())],
+        )
+    }
+
+    #[test]
+    fn restore_word() {
+        let mut u = User::new(1);
+        u.translates.push(translate("word", "en", "слово"));
+        u.translates.push(translate("door", "en", "дверь"));
+        u.translates.push(translate("door", "de", "дверь"));
+        let u = DeleteWord {
+            word: "word".to_string(),
+            lang: None,
+            now: 10,
+            ..Default::default()
+        }
+        .apply(&u);
+        let mut u = DeleteWord {
+            word: "door".to_string(),
+            lang: None,
+            now: 20,
+            ..Default::default()
+        }
+        .apply(&u);
+        assert!(u.translates.is_empty());
+        assert_eq!(3, u.trash.len());
+        // added again after the delete, and then the old review history is restored
+        u.translates.push(translate("door", "en", "дверца"));
+
+        let mut expect = User::new(1);
+        expect.trash.push(Deleted {
+            translate: translate("word", "en", "слово"),
+            deleted: 10,
+            cascade: None,
+        });
+        expect.translates.push(translate("door", "de", "дверь"));
+        let mut door = translate("door", "en", "дверь");
+        door.translates
+            .push(Word::new("дверца", "ru".parse().unwrap()));
+        expect.translates.push(door);
+        let strat = RestoreWord::default();
+        let u = strat.apply(&u);
+        assert_eq!(expect, u);
+        assert_eq!(2, strat.restored.borrow().len());
+
+        let strat = RestoreWord {
+            word: Some("word".to_string()),
+            ..Default::default()
+        };
+        let u = strat.apply(&u);
+        assert!(u.trash.is_empty());
+        assert_eq!(3, u.translates.len());
+        assert_eq!(
+            vec![translate("word", "en", "слово")],
+            *strat.restored.borrow()
+        );
+        let strat = RestoreWord::default();
+        assert_eq!(u, strat.apply(&u));
+        assert!(strat.restored.borrow().is_empty());
+    }
+
+    #[test]
+    fn del_word_lang() {
+        let mut u = User::new(1);
+        u.translates.push(translate("die", "de", "эта"));
+        u.translates.push(translate("die", "en", "умереть"));
+        let strat = DeleteWord {
+            word: "die".to_string(),
+            lang: Some("de".parse().unwrap()),
+            now: 10,
+            ..Default::default()
+        };
+        let mut expect = User::new(1);
+        expect.translates.push(translate("die", "en", "умереть"));
+        expect.trash.push(Deleted {
+            translate: translate("die", "de", "эта"),
+            deleted: 10,
+            cascade: None,
+        });
+        assert_eq!(expect, strat.apply(&u));
+        assert_eq!(1, strat.deleted.get());
+        strat.apply(&expect);
+        assert_eq!(0, strat.deleted.get());
+    }
+
+    #[test]
+    fn del_lang_cascade() {
+        let mut u = User::new(1);
+        u.langs.push("en".parse().unwrap());
+        u.langs.push("de".parse().unwrap());
+        u.translates.push(translate("die", "de", "эта"));
+        let mut word = translate("word", "en", "слово");
+        word.translates
+            .push(Word::new("Wort", "de".parse().unwrap()));
+        u.translates.push(word);
+
+        let mut expect = User::new(1);
+        expect.langs.push("en".parse().unwrap());
+        expect.translates.push(translate("word", "en", "слово"));
+        let mut strat = DeleteLang {
+            lang: "de".parse().unwrap(),
+            cascade: Some(Cascade::Delete),
+            now: 10,
+        };
+        assert_eq!(expect, strat.apply(&u));
+
+        expect.trash.push(Deleted {
+            translate: translate("die", "de", "эта"),
+            deleted: 10,
+            cascade: Some("de".parse().unwrap()),
+        });
+        expect.trash.push(Deleted {
+            translate: Translate::new(
+                Word::new("word", "en".parse().unwrap()),
+                vec![Word::new("Wort", "de".parse().unwrap())],
+            ),
+            deleted: 10,
+            cascade: Some("de".parse().unwrap()),
+        });
+        strat.cascade = Some(Cascade::Archive);
+        let archived = strat.apply(&u);
+        assert_eq!(expect, archived);
+
+        // a later delete of another word isn't a part of the batch
+        let archived = DeleteWord {
+            word: "word".to_string(),
+            lang: None,
+            now: 10,
+            ..Default::default()
+        }
+        .apply(&archived);
+        let restored = RestoreWord::default().apply(&archived);
+        assert_eq!(2, restored.trash.len());
+        assert_eq!(vec![translate("word", "en", "слово")], restored.translates);
+        // the language, its words and translations into it come back together
+        let restored = RestoreWord::default().apply(&restored);
+        assert!(restored.trash.is_empty());
+        assert_eq!(u.langs, restored.langs);
+        assert_eq!(
+            vec![u.translates[1].clone(), u.translates[0].clone()],
+            restored.translates
+        );
+    }
+
+    #[test]
+    fn purge_trash() {
+        let mut u = User::new(1);
+        for (deleted, word) in [(10, "word"), (20, "door")] {
+            u.trash.push(Deleted {
+                translate: translate(word, "en", word),
+                deleted,
+                cascade: None,
+            });
+        }
+        let mut expect = u.clone();
+        expect.trash.remove(0);
+        assert_eq!(expect, PurgeTrash { before: 20 }.apply(&u));
+    }
+
+    #[test]
+    fn add_translate_case() {
+        let mut u = User::new(1);
+        u.translates.push(translate("haus", "de", "дом"));
+        let u = AddTranslate {
+            tran: translate("Haus", "de", "Дом"),
+        }
+        .apply(&u);
+
+        let mut expect = User::new(1);
+        expect.translates.push(translate("Haus", "de", "дом"));
+        assert_eq!(expect, u);
+    }
+
+    #[test]
+    fn edit_translates() {
+        let mut u = User::new(1);
+        let mut door = translate("door", "en", "дверь");
+        door.translates
+            .push(Word::new("дверца", "ru".parse().unwrap()));
+        u.translates.push(door);
+        u.translates.push(translate("word", "en", "слово"));
+
+        let u = AddManualTranslate {
+            word: "Door".to_string(),
+            tran: Word::new("Tür", "de".parse().unwrap()),
+        }
+        .apply(&u);
+        let u = AddManualTranslate {
+            word: "door".to_string(),
+            tran: Word::new("Дверца", "ru".parse().unwrap()),
+        }
+        .apply(&u);
+        let u = ReorderTranslates {
+            word: "door".to_string(),
+            order: vec!["tür".to_string(), "дверца".to_string()],
+        }
+        .apply(&u);
+        assert_eq!(
+            vec![("Tür", true), ("Дверца", true), ("дверь", false)],
+            u.translates[0]
+                .translates
+                .iter()
+                .map(|t| (t.word.as_str(), t.manual))
+                .collect::<Vec<(&str, bool)>>()
+        );
+
+        // a new machine translation doesn't replace the manual one
+        let u = AddTranslate {
+            tran: translate("door", "en", "дверца"),
+        }
+        .apply(&u);
+        assert_eq!(3, u.translates[0].translates.len());
+        assert!(u.translates[0].translates[1].manual);
+
+        let u = DeleteTranslate {
+            word: "door".to_string(),
+            tran: "дверь".to_string(),
+            lang: Some("de".parse().unwrap()),
+        }
+        .apply(&u);
+        assert_eq!(3, u.translates[0].translates.len());
+        let u = DeleteTranslate {
+            word: "door".to_string(),
+            tran: "дверь".to_string(),
+            lang: None,
+        }
+        .apply(&u);
+        assert_eq!(2, u.translates[0].translates.len());
+        assert_eq!(translate("word", "en", "слово"), u.translates[1]);
+    }
+
+    #[test]
+    fn rejected_translate() {
+        let mut u = User::new(1);
+        u.translates.push(translate("door", "en", "дверь"));
+        u.translates.push(translate("door", "de", "дверь"));
+        let u = DeleteTranslate {
+            word: "door".to_string(),
+            tran: "Дверь".to_string(),
+            lang: None,
+        }
+        .apply(&u);
+        assert!(u.translates[0].translates.is_empty());
+        assert_eq!(
+            vec![Word::new("дверь", "ru".parse().unwrap())],
+            u.translates[0].rejected
+        );
+
+        // translating the word again doesn't bring it back, new translations are added
+        let mut tran = translate("door", "en", "дверь");
+        tran.translates
+            .push(Word::new("дверца", "ru".parse().unwrap()));
+        let u = AddTranslates {
+            trans: vec![tran, translate("door", "de", "дверь")],
+        }
+        .apply(&u);
+        assert_eq!(
+            vec![Word::new("дверца", "ru".parse().unwrap())],
+            u.translates[0].translates
+        );
+        assert!(u.translates[1].translates.is_empty());
+
+        // unless the user types it
+        let u = AddManualTranslate {
+            word: "door".to_string(),
+            tran: Word::new("дверь", "ru".parse().unwrap()),
+        }
+        .apply(&u);
+        assert_eq!(2, u.translates[0].translates.len());
+        assert!(u.translates[0].rejected.is_empty());
+        assert!(u.translates[1].rejected.is_empty());
+    }
+
+    #[test]
+    fn edit_details() {
+        let mut u = User::new(1);
+        u.translates.push(translate("Haus", "de", "дом"));
+        u.translates.push(translate("word", "en", "слово"));
+        let edits = [
+            Edit::Note(Some("das Haus".to_string())),
+            Edit::Grammar(Some("noun, n".to_string())),
+            Edit::AddExample("Das Haus ist groß.".to_string()),
+            Edit::AddExample("Ich gehe nach Hause.".to_string()),
+            Edit::AddExample("Das Haus ist groß.".to_string()),
+            Edit::DeleteExample(3),
+            Edit::DeleteExample(1),
+            Edit::AddTags(vec![
+                "#Home".to_string(),
+                "a1".to_string(),
+                "home".to_string(),
+            ]),
+            Edit::DeleteTags(vec!["A1".to_string()]),
+        ];
+        for edit in edits {
+            u = EditDetails {
+                word: "haus".to_string(),
+                edit,
+            }
+            .apply(&u);
+        }
+        let expect = Details {
+            note: Some("das Haus".to_string()),
+            examples: vec!["Ich gehe nach Hause.".to_string()],
+            grammar: Some("noun, n".to_string()),
+            tags: vec!["home".to_string()],
+        };
+        assert_eq!(expect, u.translates[0].details);
+        assert_eq!(
+            "DE\tHaus (noun, n)\nRU\tдом\nNote: das Haus\n1. Ich gehe nach Hause.\n#home\n",
+            u.translates[0].to_string()
+        );
+        assert!(u.translates[0].contains("#HOME"));
+        assert!(u.translates[0].contains("hause"));
+        assert!(!u.translates[0].contains("#a1"));
+        assert_eq!(translate("word", "en", "слово"), u.translates[1]);
+
+        let u = EditDetails {
+            word: "Haus".to_string(),
+            edit: Edit::Replace(Details {
+                tags: vec!["#B1".to_string()],
+                ..Details::default()
+            }),
+        }
+        .apply(&u);
+        assert_eq!(vec!["b1"], u.translates[0].details.tags);
+        assert!(u.translates[0].details.note.is_none());
+    }
 }
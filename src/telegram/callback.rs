@@ -0,0 +1,548 @@
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::storage::schedule::Grade;
+use crate::storage::strategy::Cascade;
+use crate::storage::Word;
+use crate::telegram::client::{InlineKeyboardButton, InlineKeyboardMarkup};
+use crate::translate::Lang;
+
+const REVIEW_PREFIX: &str = "r";
+const DELETE_PREFIX: &str = "d";
+const DELETE_LANG_PREFIX: &str = "l";
+const ADD_WORD_PREFIX: &str = "a";
+// phrases longer than this don't fit into callback data and are sent as a hash,
+// the longest review payload is "r:again:126:mni-Mtei:" plus the word
+const MAX_WORD_LEN: usize = 40;
+const HASH_PREFIX: char = '#';
+
+#[derive(Debug, PartialEq)]
+pub enum Callback {
+    Review(ReviewCallback),
+    Delete(DeleteCallback),
+    DeleteLang(DeleteLangCallback),
+    AddWord(AddWordCallback),
+}
+
+// Payload of a review card button. Telegram limits callback data to 64 bytes,
+// so it is packed as "r:<grade>:<remaining>:<lang>:<word>"
+#[derive(Debug, PartialEq)]
+pub struct ReviewCallback {
+    pub grade: Grade,
+    // how many cards are left to show after this one
+    pub remaining: i8,
+    pub lang: Lang,
+    pub word: String,
+}
+
+// Choice of the word to delete when several languages have it, "d:<lang>:<word>"
+#[derive(Debug, PartialEq)]
+pub struct DeleteCallback {
+    pub lang: Lang,
+    pub word: String,
+}
+
+// Confirmation of a cascade language delete, "l:<delete|archive|cancel>:<lang>"
+#[derive(Debug, PartialEq)]
+pub struct DeleteLangCallback {
+    // None cancels the delete
+    pub cascade: Option<Cascade>,
+    pub lang: Lang,
+}
+
+// Language of a word the detector wasn't sure about, "a:<lang>:<word>"
+#[derive(Debug, PartialEq)]
+pub struct AddWordCallback {
+    pub lang: Lang,
+    pub word: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CallbackParseError {
+    pub description: String,
+}
+
+impl fmt::Display for CallbackParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl error::Error for CallbackParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+fn unknown_action() -> CallbackParseError {
+    CallbackParseError {
+        description: "Unknown action".to_string(),
+    }
+}
+
+impl FromStr for Callback {
+    type Err = CallbackParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split(':').next() {
+            Some(REVIEW_PREFIX) => Ok(Callback::Review(s.parse()?)),
+            Some(DELETE_PREFIX) => Ok(Callback::Delete(s.parse()?)),
+            Some(DELETE_LANG_PREFIX) => Ok(Callback::DeleteLang(s.parse()?)),
+            Some(ADD_WORD_PREFIX) => Ok(Callback::AddWord(s.parse()?)),
+            _ => Err(unknown_action()),
+        }
+    }
+}
+
+impl FromStr for ReviewCallback {
+    type Err = CallbackParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.splitn(5, ':').collect();
+        if parts.len() < 5 || parts[0] != REVIEW_PREFIX || parts[4].is_empty() {
+            return Err(unknown_action());
+        }
+        let grade: Grade = match parts[1].parse() {
+            Ok(g) => g,
+            Err(e) => {
+                return Err(CallbackParseError {
+                    description: format!("{}", e),
+                })
+            }
+        };
+        let remaining: i8 = match parts[2].parse() {
+            Ok(n) => n,
+            Err(_e) => {
+                return Err(CallbackParseError {
+                    description: "Can't parse number of words".to_string(),
+                })
+            }
+        };
+        let lang: Lang = match parts[3].parse() {
+            Ok(l) => l,
+            Err(e) => {
+                return Err(CallbackParseError {
+                    description: format!("{}", e),
+                })
+            }
+        };
+
+        Ok(ReviewCallback {
+            grade,
+            remaining,
+            lang,
+            word: parts[4].to_string(),
+        })
+    }
+}
+
+impl fmt::Display for ReviewCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}:{}",
+            REVIEW_PREFIX, self.grade, self.remaining, self.lang, self.word
+        )
+    }
+}
+
+impl FromStr for DeleteCallback {
+    type Err = CallbackParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.splitn(3, ':').collect();
+        if parts.len() < 3 || parts[0] != DELETE_PREFIX || parts[2].is_empty() {
+            return Err(unknown_action());
+        }
+        let lang: Lang = match parts[1].parse() {
+            Ok(l) => l,
+            Err(e) => {
+                return Err(CallbackParseError {
+                    description: format!("{}", e),
+                })
+            }
+        };
+
+        Ok(DeleteCallback {
+            lang,
+            word: parts[2].to_string(),
+        })
+    }
+}
+
+impl fmt::Display for DeleteCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", DELETE_PREFIX, self.lang.lang, self.word)
+    }
+}
+
+impl FromStr for DeleteLangCallback {
+    type Err = CallbackParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.splitn(3, ':').collect();
+        if parts.len() < 3 || parts[0] != DELETE_LANG_PREFIX {
+            return Err(unknown_action());
+        }
+        let cascade = match parts[1] {
+            "delete" => Some(Cascade::Delete),
+            "archive" => Some(Cascade::Archive),
+            "cancel" => None,
+            _ => return Err(unknown_action()),
+        };
+        let lang: Lang = match parts[2].parse() {
+            Ok(l) => l,
+            Err(e) => {
+                return Err(CallbackParseError {
+                    description: format!("{}", e),
+                })
+            }
+        };
+
+        Ok(DeleteLangCallback { cascade, lang })
+    }
+}
+
+impl fmt::Display for DeleteLangCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.cascade {
+            Some(Cascade::Delete) => "delete",
+            Some(Cascade::Archive) => "archive",
+            None => "cancel",
+        };
+        write!(f, "{}:{}:{}", DELETE_LANG_PREFIX, action, self.lang.lang)
+    }
+}
+
+impl FromStr for AddWordCallback {
+    type Err = CallbackParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.splitn(3, ':').collect();
+        if parts.len() < 3 || parts[0] != ADD_WORD_PREFIX || parts[2].is_empty() {
+            return Err(unknown_action());
+        }
+        let lang: Lang = match parts[1].parse() {
+            Ok(l) => l,
+            Err(e) => {
+                return Err(CallbackParseError {
+                    description: format!("{}", e),
+                })
+            }
+        };
+
+        Ok(AddWordCallback {
+            lang,
+            word: parts[2].to_string(),
+        })
+    }
+}
+
+impl fmt::Display for AddWordCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", ADD_WORD_PREFIX, self.lang.lang, self.word)
+    }
+}
+
+pub fn delete_lang_keyboard(lang: &Lang) -> InlineKeyboardMarkup {
+    let buttons = [
+        ("Delete", Some(Cascade::Delete)),
+        ("Archive", Some(Cascade::Archive)),
+        ("Cancel", None),
+    ]
+    .iter()
+    .map(|(text, cascade)| InlineKeyboardButton {
+        text: text.to_string(),
+        callback_data: DeleteLangCallback {
+            cascade: *cascade,
+            lang: lang.clone(),
+        }
+        .to_string(),
+    })
+    .collect();
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![buttons],
+    }
+}
+
+// The word itself or "#<hash>" of a long phrase. FNV-1a is used since it is
+// stable between builds, buttons of old messages must keep working
+pub fn word_key(word: &str) -> String {
+    if word.len() <= MAX_WORD_LEN && !word.starts_with(HASH_PREFIX) {
+        return word.to_string();
+    }
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in word.bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{}{:016x}", HASH_PREFIX, hash)
+}
+
+// The word of a key among the known ones. A hash that none of them has is an error,
+// it must never be taken for the word itself
+pub fn word_by_key(
+    key: &str,
+    words: impl IntoIterator<Item = String>,
+) -> Result<String, CallbackParseError> {
+    if word_key(key) == key {
+        return Ok(key.to_string());
+    }
+    match words.into_iter().find(|w| word_key(w) == key) {
+        Some(word) => Ok(word),
+        None => Err(CallbackParseError {
+            description: "Word not found".to_string(),
+        }),
+    }
+}
+
+// One button for every language of the word
+pub fn delete_keyboard(word: &str, langs: &[Lang]) -> InlineKeyboardMarkup {
+    let buttons = langs
+        .iter()
+        .map(|lang| InlineKeyboardButton {
+            text: format!("{} {}", word, lang.lang.to_uppercase()),
+            callback_data: DeleteCallback {
+                lang: lang.clone(),
+                word: word_key(word),
+            }
+            .to_string(),
+        })
+        .collect();
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![buttons],
+    }
+}
+
+// One button for every language the word may be in
+pub fn add_word_keyboard(word: &str, langs: &[Lang]) -> InlineKeyboardMarkup {
+    let buttons = langs
+        .iter()
+        .map(|lang| InlineKeyboardButton {
+            text: lang.name(),
+            callback_data: AddWordCallback {
+                lang: lang.clone(),
+                word: word_key(word),
+            }
+            .to_string(),
+        })
+        .collect();
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![buttons],
+    }
+}
+
+// The first row takes the language out of the phrase, the second one keeps the whole phrase
+// in one of the languages it may be in
+pub fn confirm_word_keyboard(
+    text: &str,
+    word: &str,
+    lang: &Lang,
+    langs: &[Lang],
+) -> InlineKeyboardMarkup {
+    let button = |word: &str, lang: &Lang| InlineKeyboardButton {
+        text: format!("{} {}", word, lang.lang.to_uppercase()),
+        callback_data: AddWordCallback {
+            lang: lang.clone(),
+            word: word_key(word),
+        }
+        .to_string(),
+    };
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![
+            vec![button(word, lang)],
+            langs.iter().map(|l| button(text, l)).collect(),
+        ],
+    }
+}
+
+pub fn review_keyboard(word: &Word, remaining: i8) -> InlineKeyboardMarkup {
+    let buttons = [
+        ("Again", Grade::Again),
+        ("Hard", Grade::Hard),
+        ("Good", Grade::Good),
+        ("Easy", Grade::Easy),
+    ]
+    .iter()
+    .map(|(text, grade)| InlineKeyboardButton {
+        text: text.to_string(),
+        callback_data: ReviewCallback {
+            grade: *grade,
+            remaining,
+            lang: word.lang.clone(),
+            word: word_key(&word.word),
+        }
+        .to_string(),
+    })
+    .collect();
+
+    InlineKeyboardMarkup {
+        inline_keyboard: vec![buttons],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::schedule::Grade;
+    use crate::storage::strategy::Cascade;
+    use crate::storage::Word;
+    use crate::telegram::callback::{
+        add_word_keyboard, confirm_word_keyboard, delete_keyboard, delete_lang_keyboard,
+        review_keyboard, word_by_key, word_key, AddWordCallback, Callback, CallbackParseError,
+        DeleteCallback, DeleteLangCallback, ReviewCallback,
+    };
+
+    #[test]
+    fn parse_review_callback() {
+        assert_eq!(
+            Ok(ReviewCallback {
+                grade: Grade::Hard,
+                remaining: 2,
+                lang: "en".parse().unwrap(),
+                word: "a:b".to_string(),
+            }),
+            "r:hard:2:en:a:b".parse()
+        );
+        assert_eq!(
+            Err(CallbackParseError {
+                description: "Unknown action".to_string(),
+            }),
+            "x:hard:2:en:word".parse::<ReviewCallback>()
+        );
+        assert_eq!(
+            Err(CallbackParseError {
+                description: "Unknown action".to_string(),
+            }),
+            "r:hard:2:word".parse::<ReviewCallback>()
+        );
+        assert_eq!(
+            Err(CallbackParseError {
+                description: "Unknown grade".to_string(),
+            }),
+            "r:best:2:en:word".parse::<ReviewCallback>()
+        );
+    }
+
+    #[test]
+    fn keyboard_roundtrip() {
+        let kb = review_keyboard(&Word::new("word", "de".parse().unwrap()), 1);
+        assert_eq!(4, kb.inline_keyboard[0].len());
+        let cb: ReviewCallback = kb.inline_keyboard[0][2].callback_data.parse().unwrap();
+        assert_eq!(
+            ReviewCallback {
+                grade: Grade::Good,
+                remaining: 1,
+                lang: "de".parse().unwrap(),
+                word: "word".to_string(),
+            },
+            cb
+        );
+    }
+
+    #[test]
+    fn long_phrase() {
+        let phrase = "the quick brown fox jumps over the lazy dog and the cat";
+        let kb = review_keyboard(&Word::new(phrase, "en".parse().unwrap()), 1);
+        let data = &kb.inline_keyboard[0][0].callback_data;
+        assert!(data.len() <= 64, "{}", data);
+        let cb: ReviewCallback = data.parse().unwrap();
+        assert_eq!(word_key(phrase), cb.word);
+        assert_eq!("give up", word_key("give up"));
+
+        let words = vec!["give up".to_string(), phrase.to_string()];
+        assert_eq!(Ok(phrase.to_string()), word_by_key(&cb.word, words.clone()));
+        assert_eq!(Ok("door".to_string()), word_by_key("door", words));
+        assert_eq!(
+            Err(CallbackParseError {
+                description: "Word not found".to_string(),
+            }),
+            word_by_key(&cb.word, vec!["give up".to_string()])
+        );
+    }
+
+    #[test]
+    fn parse_callback() {
+        let lang = "de".parse().unwrap();
+        assert_eq!(
+            Ok(Callback::Delete(DeleteCallback {
+                lang,
+                word: "die".to_string(),
+            })),
+            "d:de:die".parse()
+        );
+        assert!(matches!(
+            "r:good:0:de:die".parse::<Callback>(),
+            Ok(Callback::Review(_))
+        ));
+        assert_eq!(
+            Err(CallbackParseError {
+                description: "Unsupported language".to_string(),
+            }),
+            "d:dee:die".parse::<Callback>()
+        );
+        assert!("d:de:".parse::<Callback>().is_err());
+        assert!("x:de:die".parse::<Callback>().is_err());
+
+        let kb = delete_keyboard("die", &["de".parse().unwrap(), "en".parse().unwrap()]);
+        assert_eq!("die EN", kb.inline_keyboard[0][1].text);
+        assert_eq!("d:en:die", kb.inline_keyboard[0][1].callback_data);
+    }
+
+    #[test]
+    fn delete_lang_roundtrip() {
+        let lang = "de".parse().unwrap();
+        let kb = delete_lang_keyboard(&lang);
+        assert_eq!(3, kb.inline_keyboard[0].len());
+        assert_eq!("l:archive:de", kb.inline_keyboard[0][1].callback_data);
+        assert_eq!(
+            Ok(Callback::DeleteLang(DeleteLangCallback {
+                cascade: Some(Cascade::Archive),
+                lang: lang.clone(),
+            })),
+            kb.inline_keyboard[0][1].callback_data.parse()
+        );
+        assert_eq!(
+            Ok(DeleteLangCallback {
+                cascade: None,
+                lang,
+            }),
+            kb.inline_keyboard[0][2].callback_data.parse()
+        );
+        assert!("l:drop:de".parse::<Callback>().is_err());
+    }
+
+    #[test]
+    fn add_word_roundtrip() {
+        let kb = add_word_keyboard("Haus", &["de".parse().unwrap(), "en".parse().unwrap()]);
+        assert_eq!("German", kb.inline_keyboard[0][0].text);
+        assert_eq!("a:en:Haus", kb.inline_keyboard[0][1].callback_data);
+        assert_eq!(
+            Ok(Callback::AddWord(AddWordCallback {
+                lang: "en".parse().unwrap(),
+                word: "Haus".to_string(),
+            })),
+            kb.inline_keyboard[0][1].callback_data.parse()
+        );
+        assert!("a:en:".parse::<Callback>().is_err());
+    }
+
+    #[test]
+    fn confirm_word_buttons() {
+        let kb = confirm_word_keyboard(
+            "to be",
+            "to",
+            &"be".parse().unwrap(),
+            &["en".parse().unwrap()],
+        );
+        assert_eq!("to BE", kb.inline_keyboard[0][0].text);
+        assert_eq!("a:be:to", kb.inline_keyboard[0][0].callback_data);
+        assert_eq!("to be EN", kb.inline_keyboard[1][0].text);
+        assert_eq!("a:en:to be", kb.inline_keyboard[1][0].callback_data);
+    }
+}
@@ -5,7 +5,7 @@ use std::{error, time};
 use hyper;
 use hyper::body::HttpBody;
 use hyper_tls::HttpsConnector;
-use serde::{Deserialize, Serialize};
+use serde::{ser, Deserialize, Serialize, Serializer};
 
 const API_URL: &str = "https://api.telegram.org";
 
@@ -34,6 +34,18 @@ pub struct Update {
     pub message: Option<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub edited_message: Option<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CallbackQuery {
+    pub id: String,
+    pub from: User,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -47,12 +59,36 @@ pub struct Message {
     pub text: String,
     pub from: User,
     pub chat: Chat,
+    // the command a bot message answers, set in callbacks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to_message: Option<Box<Message>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct InlineKeyboardButton {
+    pub text: String,
+    pub callback_data: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct InlineKeyboardMarkup {
+    pub inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+// reply_markup is sent as a JSON-serialized object inside the form
+fn serialize_json<S: Serializer, T: Serialize>(v: &T, s: S) -> Result<S::Ok, S::Error> {
+    let j = serde_json::to_string(v).map_err(ser::Error::custom)?;
+    s.serialize_str(&j)
 }
 
 #[derive(Serialize, Debug)]
 pub struct Answer {
     pub reply_to_message_id: i64,
-    //reply_markup
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_json"
+    )]
+    pub reply_markup: Option<InlineKeyboardMarkup>,
     pub chat_id: i64,
     pub text: String,
 }
@@ -78,12 +114,32 @@ impl Answer {
     pub fn from_message(msg: &str, message: &Message) -> Answer {
         Answer {
             reply_to_message_id: message.message_id,
+            reply_markup: None,
             chat_id: message.chat.id,
             text: msg.to_string(),
         }
     }
 }
 
+#[derive(Serialize, Debug)]
+pub struct EditMessage {
+    pub chat_id: i64,
+    pub message_id: i64,
+    pub text: String,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_json"
+    )]
+    pub reply_markup: Option<InlineKeyboardMarkup>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CallbackAnswer {
+    pub callback_query_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct SendMessageResponse {
     ok: bool,
@@ -182,9 +238,36 @@ impl Client {
     }
 
     async fn send_msg_(&self, msg: &Answer) -> Result<(), Box<dyn error::Error>> {
-        let form = serde_qs::to_string(msg)?;
+        self.post_form("sendMessage", msg).await
+    }
+
+    pub async fn edit_msg(&self, msg: &EditMessage) -> Result<(), Box<dyn error::Error>> {
+        tokio::time::timeout(
+            time::Duration::from_secs(5),
+            self.post_form("editMessageText", msg),
+        )
+        .await?
+    }
+
+    pub async fn answer_callback(
+        &self,
+        answer: &CallbackAnswer,
+    ) -> Result<(), Box<dyn error::Error>> {
+        tokio::time::timeout(
+            time::Duration::from_secs(5),
+            self.post_form("answerCallbackQuery", answer),
+        )
+        .await?
+    }
 
-        let url = format!("{}/bot{}/sendMessage", API_URL, self.token);
+    async fn post_form(
+        &self,
+        method: &str,
+        data: &impl Serialize,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let form = serde_qs::to_string(data)?;
+
+        let url = format!("{}/bot{}/{}", API_URL, self.token, method);
         let https = HttpsConnector::new();
         let client = hyper::Client::builder().build::<_, hyper::Body>(https);
         let req = hyper::Request::builder()
@@ -211,3 +294,38 @@ impl Client {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::telegram::client::{Answer, InlineKeyboardButton, InlineKeyboardMarkup};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Form {
+        reply_markup: String,
+    }
+
+    #[test]
+    fn answer_form() {
+        let mut answer = Answer {
+            reply_to_message_id: 1,
+            reply_markup: None,
+            chat_id: 2,
+            text: "text".to_string(),
+        };
+        assert_eq!(
+            "reply_to_message_id=1&chat_id=2&text=text",
+            serde_qs::to_string(&answer).unwrap()
+        );
+
+        answer.reply_markup = Some(InlineKeyboardMarkup {
+            inline_keyboard: vec![vec![InlineKeyboardButton {
+                text: "Good".to_string(),
+                callback_data: "r:good:0:word".to_string(),
+            }]],
+        });
+        let form: Form = serde_qs::from_str(&serde_qs::to_string(&answer).unwrap()).unwrap();
+        let decoded: InlineKeyboardMarkup = serde_json::from_str(&form.reply_markup).unwrap();
+        assert_eq!(answer.reply_markup, Some(decoded));
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 use std::str::FromStr;
 
+use crate::storage::schedule::{Grade, GradeParseError};
+use crate::storage::strategy::Edit;
 use crate::storage::Word;
 use crate::translate;
 
@@ -10,20 +12,55 @@ const LIST_LANGS_KEYWORD: &str = "/ll";
 const ADD_LANG_KEYWORD: &str = "/l";
 const DELETE_LANG_KEYWORD: &str = "/dl";
 const LIST_WORDS_KEYWORD: &str = "/lw";
-const LIST_RANDOM_WORDS_KEYWORD: &str = "/r";
+const REVIEW_WORDS_KEYWORD: &str = "/r";
+const GRADE_WORD_KEYWORD: &str = "/g";
+const QUIZ_KEYWORD: &str = "/q";
 const ADD_WORD_KEYWORD: &str = "/w";
+const IMPORT_KEYWORD: &str = "/i";
 const DELETE_WORD_KEYWORD: &str = "/dw";
+const ADD_TRANSLATION_KEYWORD: &str = "/t";
+const DELETE_TRANSLATION_KEYWORD: &str = "/dt";
+const ORDER_TRANSLATIONS_KEYWORD: &str = "/o";
+const NOTE_KEYWORD: &str = "/n";
+const EXAMPLE_KEYWORD: &str = "/e";
+const DELETE_EXAMPLE_KEYWORD: &str = "/de";
+const GRAMMAR_KEYWORD: &str = "/gr";
+const TAG_KEYWORD: &str = "/tg";
+const DELETE_TAG_KEYWORD: &str = "/dtg";
+const UNDO_KEYWORD: &str = "/u";
 const HELP_KEYWORD: &str = "/help";
+// "/dl en all" also deletes words in the language
+const CASCADE_KEYWORD: &str = "all";
+
+const QUOTES: [char; 5] = ['"', '«', '»', '“', '”'];
 
 #[derive(Debug, PartialEq)]
 pub enum Command {
     ListLangs,
     AddLang(translate::Lang),
-    DeleteLang(translate::Lang),
+    // the flag is set to delete words in the language too
+    DeleteLang(translate::Lang, bool),
     ListWords(String),
-    ListRandomWords(i8),
-    AddWord(Word),
-    DeleteWord(String),
+    ReviewWords(i8),
+    GradeWord(String, Grade),
+    Quiz,
+    // the language is detected if it isn't given
+    AddWord(String, Option<translate::Lang>),
+    // unquoted phrase with a language in it, "to be": the phrase, the word without
+    // the language and the language. The user chooses what was meant
+    ConfirmWord(String, String, translate::Lang),
+    // words of one language, separated by spaces, commas or new lines
+    Import(translate::Lang, Vec<String>),
+    DeleteWord(String, Option<translate::Lang>),
+    // word and the user's translation of it
+    AddTranslation(String, Word),
+    // word, wrong translation and optionally its language
+    DeleteTranslation(String, String, Option<translate::Lang>),
+    // word and its translations in the wanted order
+    OrderTranslations(String, Vec<String>),
+    // note, example, grammar or tags of a word
+    EditWord(String, Edit),
+    Undo(String),
     Help,
 }
 
@@ -46,32 +83,179 @@ impl From<translate::LangParseError> for CommandParseError {
     }
 }
 
+impl From<GradeParseError> for CommandParseError {
+    fn from(e: GradeParseError) -> Self {
+        CommandParseError {
+            description: e.description,
+        }
+    }
+}
+
+// Splits arguments by spaces, text in quotes is one argument: /w "give up" en
+fn split_args(s: &str) -> Vec<String> {
+    let mut args: Vec<String> = vec![];
+    let mut arg = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        if QUOTES.contains(&c) {
+            if !arg.trim().is_empty() {
+                args.push(arg.trim().to_string());
+            }
+            arg.clear();
+            quoted = !quoted;
+        } else if c.is_whitespace() {
+            if quoted {
+                if !arg.is_empty() && !arg.ends_with(' ') {
+                    arg.push(' ')
+                }
+            } else if !arg.is_empty() {
+                args.push(arg.clone());
+                arg.clear();
+            }
+        } else {
+            arg.push(c)
+        }
+    }
+    if !arg.trim().is_empty() {
+        args.push(arg.trim().to_string())
+    }
+
+    args
+}
+
+// The language goes after the word or phrase, "give up en", or before it, "de auf wiedersehen".
+// Without a known language all arguments are the word. In an unquoted phrase the language
+// may be a word of it, "to be", "take it", so it is only taken for sure from quoted input
+fn add_word(args: &[String], quoted: bool) -> Result<Command, CommandParseError> {
+    if args.is_empty() {
+        return Err(CommandParseError {
+            description: "Not enough data to add new word".to_string(),
+        });
+    }
+    if args.len() == 1 {
+        return Ok(Command::AddWord(args[0].to_string(), None));
+    }
+    let last = args.len() - 1;
+    let found = match (args[last].parse(), args[0].parse()) {
+        (Ok(lang), _) => Some((args[..last].join(" "), lang)),
+        (_, Ok(lang)) => Some((args[1..].join(" "), lang)),
+        _ => None,
+    };
+
+    Ok(match found {
+        Some((word, lang)) if quoted => Command::AddWord(word, Some(lang)),
+        Some((word, lang)) => Command::ConfirmWord(args.join(" "), word, lang),
+        None => Command::AddWord(args.join(" "), None),
+    })
+}
+
+// The word, in quotes if it is a phrase, and the text after it as it was typed:
+// /e Haus Das Haus ist groß.
+fn word_and_text(s: &str) -> (String, String) {
+    let s = s.trim_start();
+    match s.chars().next() {
+        Some(q) if QUOTES.contains(&q) => {
+            let rest = &s[q.len_utf8()..];
+            match rest.char_indices().find(|(_, c)| QUOTES.contains(c)) {
+                Some((i, c)) => (
+                    split_args(&rest[..i]).join(" "),
+                    rest[i + c.len_utf8()..].trim().to_string(),
+                ),
+                None => (split_args(rest).join(" "), "".to_string()),
+            }
+        }
+        _ => match s.find(char::is_whitespace) {
+            Some(i) => (s[..i].to_string(), s[i..].trim().to_string()),
+            None => (s.to_string(), "".to_string()),
+        },
+    }
+}
+
+// Edits of word details, the text is everything after the word
+fn edit_word(keyword: &str, s: &str) -> Result<Command, CommandParseError> {
+    let (word, text) = word_and_text(s);
+    if word.is_empty() {
+        return Err(CommandParseError {
+            description: "No word".to_string(),
+        });
+    }
+    let optional = if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    };
+    let tags: Vec<String> = text
+        .split([',', ' ', '\n'])
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_string())
+        .collect();
+    let edit = match keyword {
+        NOTE_KEYWORD => Edit::Note(optional),
+        GRAMMAR_KEYWORD => Edit::Grammar(optional),
+        EXAMPLE_KEYWORD if !text.is_empty() => Edit::AddExample(text),
+        DELETE_EXAMPLE_KEYWORD => match text.parse() {
+            Ok(n) => Edit::DeleteExample(n),
+            Err(_e) => {
+                return Err(CommandParseError {
+                    description: "Can't parse number of example".to_string(),
+                })
+            }
+        },
+        TAG_KEYWORD if !tags.is_empty() => Edit::AddTags(tags),
+        DELETE_TAG_KEYWORD if !tags.is_empty() => Edit::DeleteTags(tags),
+        _ => {
+            return Err(CommandParseError {
+                description: "Not enough data to edit word".to_string(),
+            })
+        }
+    };
+
+    Ok(Command::EditWord(word, edit))
+}
+
+// Words of an import are separated by commas, semicolons or new lines,
+// or by spaces if there are none of them
+fn import_words(s: &str) -> Vec<String> {
+    if !s.contains([',', ';', '\n']) {
+        return split_args(s);
+    }
+    s.split([',', ';', '\n'])
+        .map(|w| split_args(w).join(" "))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
 impl FromStr for Command {
     type Err = CommandParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let re = Regex::new(r"\s+").unwrap();
-        let mut clear_s = s.to_lowercase().to_string();
-        clear_s = clear_s.trim().to_string();
+        // only keywords are case-insensitive, words keep their case
+        let mut clear_s = s.trim().to_string();
         clear_s = re.replace_all(&clear_s, " ").to_string();
-        let parts: Vec<&str> = clear_s.split(" ").collect();
+        let parts: Vec<String> = split_args(&clear_s);
 
         if parts.is_empty() {
             return Err(CommandParseError {
                 description: "Command is empty".to_string(),
             });
         }
-        let cmd = match parts[0] {
-            ADD_WORD_KEYWORD => {
-                if parts.len() < 3 {
+        let cmd = match parts[0].to_lowercase().as_str() {
+            ADD_WORD_KEYWORD => add_word(&parts[1..], clear_s.contains(QUOTES))?,
+            IMPORT_KEYWORD => {
+                // new lines are kept, they separate words
+                let raw = s.trim();
+                let re = Regex::new(r"^\S+\s+\S+\s*").unwrap();
+                let words = match re.find(raw) {
+                    Some(m) if parts.len() > 2 => import_words(&raw[m.end()..]),
+                    _ => vec![],
+                };
+                if words.is_empty() {
                     return Err(CommandParseError {
-                        description: "Not enough data to add new word".to_string(),
+                        description: "Not enough data to import words".to_string(),
                     });
                 }
-                Command::AddWord(Word {
-                    word: parts[1].to_string(),
-                    lang: parts[2].parse()?,
-                })
+                Command::Import(parts[1].parse()?, words)
             }
             DELETE_WORD_KEYWORD => {
                 if parts.len() == 1 {
@@ -79,15 +263,74 @@ impl FromStr for Command {
                         description: "No word".to_string(),
                     });
                 }
-                Command::DeleteWord(parts[1].to_string())
+                let last = parts.len() - 1;
+                match parts[last].parse() {
+                    Ok(lang) if last > 1 => {
+                        Command::DeleteWord(parts[1..last].join(" "), Some(lang))
+                    }
+                    _ => Command::DeleteWord(parts[1..].join(" "), None),
+                }
             }
-            LIST_WORDS_KEYWORD => {
-                let mut pt = "".to_string();
-                if parts.len() > 1 {
-                    pt = parts[1].to_string()
+            ADD_TRANSLATION_KEYWORD => {
+                if parts.len() < 4 {
+                    return Err(CommandParseError {
+                        description: "Not enough data to add translation".to_string(),
+                    });
+                }
+                if parts.len() > 4 {
+                    return Err(CommandParseError {
+                        description: "Put phrases in quotes".to_string(),
+                    });
                 }
-                Command::ListWords(pt)
+                Command::AddTranslation(
+                    parts[1].to_string(),
+                    Word {
+                        word: parts[2].to_string(),
+                        lang: parts[3].parse()?,
+                        pos: None,
+                        manual: true,
+                    },
+                )
             }
+            DELETE_TRANSLATION_KEYWORD => match parts.len() {
+                3 => Command::DeleteTranslation(parts[1].to_string(), parts[2].to_string(), None),
+                4 => Command::DeleteTranslation(
+                    parts[1].to_string(),
+                    parts[2].to_string(),
+                    Some(parts[3].parse()?),
+                ),
+                n if n < 3 => {
+                    return Err(CommandParseError {
+                        description: "Not enough data to delete translation".to_string(),
+                    })
+                }
+                _ => {
+                    return Err(CommandParseError {
+                        description: "Put phrases in quotes".to_string(),
+                    })
+                }
+            },
+            ORDER_TRANSLATIONS_KEYWORD => {
+                if parts.len() < 3 {
+                    return Err(CommandParseError {
+                        description: "Not enough data to order translations".to_string(),
+                    });
+                }
+                Command::OrderTranslations(parts[1].to_string(), parts[2..].to_vec())
+            }
+            keyword @ (NOTE_KEYWORD
+            | EXAMPLE_KEYWORD
+            | DELETE_EXAMPLE_KEYWORD
+            | GRAMMAR_KEYWORD
+            | TAG_KEYWORD
+            | DELETE_TAG_KEYWORD) => {
+                // new lines and quotes in the text are kept
+                let raw = s.trim();
+                let text = raw.split_once(char::is_whitespace).map_or("", |(_, t)| t);
+                edit_word(keyword, text)?
+            }
+            UNDO_KEYWORD => Command::Undo(parts[1..].join(" ")),
+            LIST_WORDS_KEYWORD => Command::ListWords(parts[1..].join(" ")),
             ADD_LANG_KEYWORD => {
                 let mut l = "".to_string();
                 if parts.len() > 1 {
@@ -101,9 +344,18 @@ impl FromStr for Command {
                 if parts.len() > 1 {
                     pt = parts[1].to_string()
                 }
-                Command::DeleteLang(pt.parse()?)
+                let mut cascade = false;
+                if parts.len() > 2 {
+                    if parts[2].to_lowercase() != CASCADE_KEYWORD {
+                        return Err(CommandParseError {
+                            description: format!("Unknown option, use: {}", CASCADE_KEYWORD),
+                        });
+                    }
+                    cascade = true
+                }
+                Command::DeleteLang(pt.parse()?, cascade)
             }
-            LIST_RANDOM_WORDS_KEYWORD => {
+            REVIEW_WORDS_KEYWORD => {
                 let mut n: i8 = 3;
                 if parts.len() > 1 {
                     n = match parts[1].to_string().parse() {
@@ -115,8 +367,18 @@ impl FromStr for Command {
                         }
                     }
                 }
-                Command::ListRandomWords(n)
+                Command::ReviewWords(n)
             }
+            GRADE_WORD_KEYWORD => {
+                if parts.len() < 3 {
+                    return Err(CommandParseError {
+                        description: "Not enough data to grade word".to_string(),
+                    });
+                }
+                let last = parts.len() - 1;
+                Command::GradeWord(parts[1..last].join(" "), parts[last].parse()?)
+            }
+            QUIZ_KEYWORD => Command::Quiz,
             HELP_KEYWORD => Command::Help,
             _ => {
                 return Err(CommandParseError {
@@ -136,22 +398,116 @@ impl Command {
                 "List all supported languages".to_string()
             }
             Command::AddLang(_) => {
-                format!("Add new language. Example: {} en", ADD_LANG_KEYWORD).to_string()
+                format!(
+                    "Add new language by code or name. Example: {} en",
+                    ADD_LANG_KEYWORD
+                )
             }
-            Command::DeleteLang(_) => {
-                format!("Delete language. Example: {} en", DELETE_LANG_KEYWORD).to_string()
+            Command::DeleteLang(_, _) => {
+                format!(
+                    "Delete language, with {} also its words and translations. Example: {} en {}",
+                    CASCADE_KEYWORD, DELETE_LANG_KEYWORD, CASCADE_KEYWORD
+                )
             }
             Command::ListWords(_) => {
                 format!("List all words. Example: {} word", LIST_WORDS_KEYWORD).to_string()
             }
-            Command::ListRandomWords(_) => {
-                format!("List random words. Example: {} 5", LIST_RANDOM_WORDS_KEYWORD).to_string()
+            Command::ReviewWords(_) => {
+                format!(
+                    "Review words due for repetition. Example: {} 5",
+                    REVIEW_WORDS_KEYWORD
+                )
+            }
+            Command::GradeWord(_, _) => {
+                format!(
+                    "Grade reviewed word: again, hard, good or easy. Example: {} word good",
+                    GRADE_WORD_KEYWORD
+                )
+            }
+            Command::Quiz => {
+                format!(
+                    "Start quiz, answer with a translation. Any command stops it. Example: {}",
+                    QUIZ_KEYWORD
+                )
             }
-            Command::AddWord(_) => {
-                format!("Add new word. Example: {} word en", ADD_WORD_KEYWORD).to_string()
+            Command::AddWord(_, _) | Command::ConfirmWord(_, _, _) => {
+                format!(
+                    "Add new word or phrase, the language is detected if omitted, quote the word to give it. Example: {} \"word\" en, {} \"give up\" en, {} de \"auf Wiedersehen\" or {} Haus",
+                    ADD_WORD_KEYWORD, ADD_WORD_KEYWORD, ADD_WORD_KEYWORD, ADD_WORD_KEYWORD
+                )
             }
-            Command::DeleteWord(_) => {
-                format!("Delete word. Example: {} word", DELETE_WORD_KEYWORD).to_string()
+            Command::Import(_, _) => {
+                format!(
+                    "Add a list of words in one language. Example: {} en door, window, roof",
+                    IMPORT_KEYWORD
+                )
+            }
+            Command::DeleteWord(_, _) => {
+                format!(
+                    "Delete word, in one language if it is given. Example: {} word en",
+                    DELETE_WORD_KEYWORD
+                )
+            }
+            Command::AddTranslation(_, _) => {
+                format!(
+                    "Add your translation of a word, it is never replaced by machine ones. Example: {} Haus дом ru",
+                    ADD_TRANSLATION_KEYWORD
+                )
+            }
+            Command::DeleteTranslation(_, _, _) => {
+                format!(
+                    "Delete a wrong translation of a word. Example: {} Haus house",
+                    DELETE_TRANSLATION_KEYWORD
+                )
+            }
+            Command::OrderTranslations(_, _) => {
+                format!(
+                    "Put translations of a word first in the given order. Example: {} Haus дом здание",
+                    ORDER_TRANSLATIONS_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::Note(_)) => {
+                format!(
+                    "Set a note of a word, remove it without text. Example: {} Haus das Haus, die Häuser",
+                    NOTE_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::AddExample(_)) => {
+                format!(
+                    "Add an example sentence. Example: {} Haus Das Haus ist groß.",
+                    EXAMPLE_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::DeleteExample(_)) => {
+                format!(
+                    "Delete an example by its number. Example: {} Haus 1",
+                    DELETE_EXAMPLE_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::Grammar(_)) => {
+                format!(
+                    "Set gender or part of speech of a word. Example: {} Haus noun, n",
+                    GRAMMAR_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::AddTags(_)) => {
+                format!(
+                    "Tag a word, find tagged words with {} #tag. Example: {} Haus home a1",
+                    LIST_WORDS_KEYWORD, TAG_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::DeleteTags(_)) => {
+                format!(
+                    "Remove tags of a word. Example: {} Haus a1",
+                    DELETE_TAG_KEYWORD
+                )
+            }
+            Command::EditWord(_, Edit::Replace(_)) => "Replace all details of a word".to_string(),
+            Command::Undo(_) => {
+                format!(
+                    "Restore deleted word, the last deleted if no word. Example: {} word",
+                    UNDO_KEYWORD
+                )
             }
             Command::Help => {
                 format!("Print help. Example {}", HELP_KEYWORD)
@@ -162,6 +518,8 @@ impl Command {
 
 #[cfg(test)]
 mod tests {
+    use crate::storage::schedule::Grade;
+    use crate::storage::strategy::Edit;
     use crate::storage::Word;
     use crate::telegram::commands::{Command, CommandParseError};
     use crate::translate::Lang;
@@ -176,43 +534,105 @@ mod tests {
                 description: "Unknown command".to_string(),
             }),
         );
+        table.insert(
+            "/w \"word\" en".to_string(),
+            Ok(Command::AddWord(
+                "word".to_string(),
+                Some("en".parse().unwrap()),
+            )),
+        );
         table.insert(
             "/w word en".to_string(),
-            Ok(Command::AddWord(Word {
-                word: "word".to_string(),
-                lang: Lang {
-                    lang: "en".to_string(),
-                },
-            })),
+            Ok(Command::ConfirmWord(
+                "word en".to_string(),
+                "word".to_string(),
+                "en".parse().unwrap(),
+            )),
         );
         table.insert(
-            "/w word en ru".to_string(),
-            Ok(Command::AddWord(Word {
-                word: "word".to_string(),
-                lang: Lang {
-                    lang: "en".to_string(),
-                },
-            })),
+            "/w give  up en".to_string(),
+            Ok(Command::ConfirmWord(
+                "give up en".to_string(),
+                "give up".to_string(),
+                "en".parse().unwrap(),
+            )),
+        );
+        table.insert(
+            "/w de auf Wiedersehen".to_string(),
+            Ok(Command::ConfirmWord(
+                "de auf Wiedersehen".to_string(),
+                "auf Wiedersehen".to_string(),
+                "de".parse().unwrap(),
+            )),
+        );
+        table.insert(
+            "/w de \"auf Wiedersehen\"".to_string(),
+            Ok(Command::AddWord(
+                "auf Wiedersehen".to_string(),
+                Some("de".parse().unwrap()),
+            )),
+        );
+        // codes that are words of the phrase too
+        for (text, word, lang) in [
+            ("to be", "to", "be"),
+            ("oh no", "oh", "no"),
+            ("be careful", "careful", "be"),
+            ("take it", "take", "it"),
+            ("say hi", "say", "hi"),
+        ] {
+            table.insert(
+                format!("/w {}", text),
+                Ok(Command::ConfirmWord(
+                    text.to_string(),
+                    word.to_string(),
+                    lang.parse().unwrap(),
+                )),
+            );
+        }
+        table.insert(
+            "/w \"let it be\" en".to_string(),
+            Ok(Command::AddWord(
+                "let it be".to_string(),
+                Some("en".parse().unwrap()),
+            )),
         );
         table.insert(
             "/w word enn".to_string(),
-            Err(CommandParseError {
-                description: "Unsupported language".to_string(),
-            }),
+            Ok(Command::AddWord("word enn".to_string(), None)),
         );
         table.insert(
-            "/w word".to_string(),
+            "/w Haus".to_string(),
+            Ok(Command::AddWord("Haus".to_string(), None)),
+        );
+        table.insert(
+            "/w".to_string(),
             Err(CommandParseError {
                 description: "Not enough data to add new word".to_string(),
             }),
         );
         table.insert(
             "/dw word".to_string(),
-            Ok(Command::DeleteWord("word".to_string())),
+            Ok(Command::DeleteWord("word".to_string(), None)),
+        );
+        table.insert(
+            "/dw word en".to_string(),
+            Ok(Command::DeleteWord(
+                "word".to_string(),
+                Some(Lang {
+                    lang: "en".to_string(),
+                }),
+            )),
         );
         table.insert(
-            "/dw word word1".to_string(),
-            Ok(Command::DeleteWord("word".to_string())),
+            "/dw give up".to_string(),
+            Ok(Command::DeleteWord("give up".to_string(), None)),
+        );
+        table.insert(
+            "/dw «let it be» en".to_string(),
+            Ok(Command::DeleteWord(
+                "let it be".to_string(),
+                Some("en".parse().unwrap()),
+            )),
         );
         table.insert(
             "/dw".to_string(),
@@ -220,6 +640,105 @@ mod tests {
                 description: "No word".to_string(),
             }),
         );
+        table.insert("/u".to_string(), Ok(Command::Undo("".to_string())));
+        table.insert("/u word".to_string(), Ok(Command::Undo("word".to_string())));
+        table.insert(
+            "/t Haus \"dwelling house\" en".to_string(),
+            Ok(Command::AddTranslation(
+                "Haus".to_string(),
+                Word {
+                    word: "dwelling house".to_string(),
+                    lang: "en".parse().unwrap(),
+                    pos: None,
+                    manual: true,
+                },
+            )),
+        );
+        table.insert(
+            "/t Haus dwelling house en".to_string(),
+            Err(CommandParseError {
+                description: "Put phrases in quotes".to_string(),
+            }),
+        );
+        table.insert(
+            "/t Haus house".to_string(),
+            Err(CommandParseError {
+                description: "Not enough data to add translation".to_string(),
+            }),
+        );
+        table.insert(
+            "/dt Haus house".to_string(),
+            Ok(Command::DeleteTranslation(
+                "Haus".to_string(),
+                "house".to_string(),
+                None,
+            )),
+        );
+        table.insert(
+            "/dt Haus house EN".to_string(),
+            Ok(Command::DeleteTranslation(
+                "Haus".to_string(),
+                "house".to_string(),
+                Some("en".parse().unwrap()),
+            )),
+        );
+        table.insert(
+            "/e \"auf Wiedersehen\" «Auf Wiedersehen!», sagte er.\nUnd ging.".to_string(),
+            Ok(Command::EditWord(
+                "auf Wiedersehen".to_string(),
+                Edit::AddExample("«Auf Wiedersehen!», sagte er.\nUnd ging.".to_string()),
+            )),
+        );
+        table.insert(
+            "/n Haus".to_string(),
+            Ok(Command::EditWord("Haus".to_string(), Edit::Note(None))),
+        );
+        table.insert(
+            "/gr Haus noun, n".to_string(),
+            Ok(Command::EditWord(
+                "Haus".to_string(),
+                Edit::Grammar(Some("noun, n".to_string())),
+            )),
+        );
+        table.insert(
+            "/de Haus 2".to_string(),
+            Ok(Command::EditWord(
+                "Haus".to_string(),
+                Edit::DeleteExample(2),
+            )),
+        );
+        table.insert(
+            "/de Haus first".to_string(),
+            Err(CommandParseError {
+                description: "Can't parse number of example".to_string(),
+            }),
+        );
+        table.insert(
+            "/tg Haus #home, a1".to_string(),
+            Ok(Command::EditWord(
+                "Haus".to_string(),
+                Edit::AddTags(vec!["#home".to_string(), "a1".to_string()]),
+            )),
+        );
+        table.insert(
+            "/dtg Haus".to_string(),
+            Err(CommandParseError {
+                description: "Not enough data to edit word".to_string(),
+            }),
+        );
+        table.insert(
+            "/e".to_string(),
+            Err(CommandParseError {
+                description: "No word".to_string(),
+            }),
+        );
+        table.insert(
+            "/o Haus дом здание".to_string(),
+            Ok(Command::OrderTranslations(
+                "Haus".to_string(),
+                vec!["дом".to_string(), "здание".to_string()],
+            )),
+        );
         table.insert("/lw".to_string(), Ok(Command::ListWords("".to_string())));
         table.insert(
             "/lw wo".to_string(),
@@ -227,7 +746,11 @@ mod tests {
         );
         table.insert(
             "/lw wo w".to_string(),
-            Ok(Command::ListWords("wo".to_string())),
+            Ok(Command::ListWords("wo w".to_string())),
+        );
+        table.insert(
+            "/g give up good".to_string(),
+            Ok(Command::GradeWord("give up".to_string(), Grade::Good)),
         );
         table.insert(
             "/l en".to_string(),
@@ -243,9 +766,27 @@ mod tests {
         );
         table.insert(
             "/dl en".to_string(),
-            Ok(Command::DeleteLang(Lang {
-                lang: "en".to_string(),
-            })),
+            Ok(Command::DeleteLang(
+                Lang {
+                    lang: "en".to_string(),
+                },
+                false,
+            )),
+        );
+        table.insert(
+            "/dl en all".to_string(),
+            Ok(Command::DeleteLang(
+                Lang {
+                    lang: "en".to_string(),
+                },
+                true,
+            )),
+        );
+        table.insert(
+            "/dl en words".to_string(),
+            Err(CommandParseError {
+                description: "Unknown option, use: all".to_string(),
+            }),
         );
         table.insert(
             "/dl enn".to_string(),
@@ -253,16 +794,77 @@ mod tests {
                 description: "Unsupported language".to_string(),
             }),
         );
-        table.insert("/r".to_string(), Ok(Command::ListRandomWords(3)));
-        table.insert("/r 5".to_string(), Ok(Command::ListRandomWords(5)));
-        table.insert("/r  5   ".to_string(), Ok(Command::ListRandomWords(5)));
+        table.insert("/r".to_string(), Ok(Command::ReviewWords(3)));
+        table.insert("/r 5".to_string(), Ok(Command::ReviewWords(5)));
+        table.insert("/r  5   ".to_string(), Ok(Command::ReviewWords(5)));
         table.insert(
             "/r 1f".to_string(),
             Err(CommandParseError {
                 description: "Can't parse number of words".to_string(),
             }),
         );
+        table.insert(
+            "/g word good".to_string(),
+            Ok(Command::GradeWord("word".to_string(), Grade::Good)),
+        );
+        table.insert(
+            "/g word".to_string(),
+            Err(CommandParseError {
+                description: "Not enough data to grade word".to_string(),
+            }),
+        );
+        table.insert(
+            "/g word best".to_string(),
+            Err(CommandParseError {
+                description: "Unknown grade".to_string(),
+            }),
+        );
+        table.insert("/q".to_string(), Ok(Command::Quiz));
         table.insert("/ll".to_string(), Ok(Command::ListLangs));
+        table.insert(
+            "/W Haus DE".to_string(),
+            Ok(Command::ConfirmWord(
+                "Haus DE".to_string(),
+                "Haus".to_string(),
+                "de".parse().unwrap(),
+            )),
+        );
+        table.insert(
+            "/l Deutsch".to_string(),
+            Ok(Command::AddLang("de".parse().unwrap())),
+        );
+        table.insert(
+            "/i en door, window\nroof,,wall".to_string(),
+            Ok(Command::Import(
+                "en".parse().unwrap(),
+                vec![
+                    "door".to_string(),
+                    "window".to_string(),
+                    "roof".to_string(),
+                    "wall".to_string(),
+                ],
+            )),
+        );
+        table.insert(
+            "/i de auf Wiedersehen\nguten  Tag".to_string(),
+            Ok(Command::Import(
+                "de".parse().unwrap(),
+                vec!["auf Wiedersehen".to_string(), "guten Tag".to_string()],
+            )),
+        );
+        table.insert(
+            "/i en door \"give up\"".to_string(),
+            Ok(Command::Import(
+                "en".parse().unwrap(),
+                vec!["door".to_string(), "give up".to_string()],
+            )),
+        );
+        table.insert(
+            "/i en".to_string(),
+            Err(CommandParseError {
+                description: "Not enough data to import words".to_string(),
+            }),
+        );
         for (command, expect) in table.iter() {
             let v: Result<self::Command, CommandParseError> = command.parse();
             assert_eq!(expect, &v, "Command: {}", command)
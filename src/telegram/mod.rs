@@ -1,3 +1,5 @@
+pub mod callback;
 pub mod client;
 pub mod commands;
+pub mod quiz;
 pub mod updates;
@@ -0,0 +1,85 @@
+use crate::storage::Translate;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+pub struct Session {
+    pub translate: Translate,
+}
+
+impl Session {
+    pub fn new(translate: Translate) -> Session {
+        Session { translate }
+    }
+
+    pub fn question(&self) -> String {
+        format!(
+            "Translate: {}\t{}",
+            self.translate.word.lang.lang.to_uppercase(),
+            self.translate.word.word
+        )
+    }
+
+    pub fn check(&self, answer: &str) -> bool {
+        let answer = normalize(answer);
+        if answer.is_empty() {
+            return false;
+        }
+        self.translate
+            .translates
+            .iter()
+            .any(|w| normalize(&w.word) == answer)
+    }
+
+    pub fn expected(&self) -> String {
+        let words: Vec<String> = self
+            .translate
+            .translates
+            .iter()
+            .map(|w| format!("{}\t{}", w.lang.lang.to_uppercase(), w.word))
+            .collect();
+        words.join("\n")
+    }
+}
+
+// Lowercase, strip diacritics and collapse whitespace so "Straße" matches "strasse"
+// and "ёлка" matches "елка"
+pub fn normalize(s: &str) -> String {
+    let folded: String = s
+        .to_lowercase()
+        .replace('ß', "ss")
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect();
+    folded.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::storage::{Translate, Word};
+    use crate::telegram::quiz::{normalize, Session};
+
+    #[test]
+    fn normalize_answer() {
+        assert_eq!("strasse", normalize("Straße"));
+        assert_eq!("елка", normalize(" Ёлка "));
+        assert_eq!("cafe au lait", normalize("Café  au   lait"));
+        assert_eq!("uber", normalize("ÜBER"));
+    }
+
+    #[test]
+    fn check_answer() {
+        let session = Session::new(Translate::new(
+            Word::new("street", "en".parse().unwrap()),
+            vec![
+                Word::new("Straße", "de".parse().unwrap()),
+                Word::new("улица", "ru".parse().unwrap()),
+            ],
+        ));
+        assert!(session.check("strasse"));
+        assert!(session.check("STRASSE "));
+        assert!(session.check("Улица"));
+        assert!(!session.check("street"));
+        assert!(!session.check(""));
+    }
+}
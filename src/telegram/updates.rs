@@ -1,22 +1,33 @@
-use std::collections::HashSet;
-use std::sync::{Arc, RwLock};
+use std::collections::HashMap;
+use std::error;
+use std::sync::Arc;
 use std::thread::sleep;
 use std::time;
 use std::time::Duration;
 
-use crate::storage::Word;
+use crate::storage;
+use crate::storage::schedule::Grade;
+use crate::storage::strategy::Edit;
+use crate::storage::{same_word, Word};
+use crate::telegram::callback::{
+    add_word_keyboard, confirm_word_keyboard, delete_keyboard, delete_lang_keyboard,
+    review_keyboard, word_by_key, word_key, AddWordCallback, Callback, DeleteCallback,
+    DeleteLangCallback, ReviewCallback,
+};
 use crate::telegram::client;
-use crate::user::user::UserWords;
+use crate::telegram::quiz;
+use crate::translate::{langs, Lang};
+use crate::user::user::{Detected, UserWords};
 
 use crate::telegram::commands::Command;
 use log::{error, warn};
-use rand;
-use rand::Rng;
-use tokio::runtime::Builder;
+use rand::seq::SliceRandom;
+use tokio::runtime::{Builder, Runtime};
 
-pub fn updates_processing(user_words: Arc<RwLock<UserWords>>, token: String) {
+pub fn updates_processing(user_words: Arc<UserWords>, token: String) {
     let mut cli = client::Client::new(&token);
     let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    let mut quizzes: HashMap<i64, quiz::Session> = HashMap::new();
     loop {
         let updates = match rt.block_on(cli.get_updates(time::Duration::from_secs(60))) {
             Ok(updates) => updates,
@@ -27,6 +38,10 @@ pub fn updates_processing(user_words: Arc<RwLock<UserWords>>, token: String) {
             }
         };
         for update in updates {
+            if let Some(query) = update.callback_query {
+                callback_processing(&rt, &cli, user_words.clone(), &query);
+                continue;
+            }
             let message = match update.message {
                 Some(msg) => msg,
                 None => match update.edited_message {
@@ -34,6 +49,16 @@ pub fn updates_processing(user_words: Arc<RwLock<UserWords>>, token: String) {
                     None => continue,
                 },
             };
+            // any command stops the quiz, plain text is an answer to the current question
+            let session = quizzes.remove(&message.chat.id);
+            if let Some(session) = session {
+                if !message.text.trim_start().starts_with('/') {
+                    let answer_res =
+                        quiz_answer(user_words.clone(), &message, &session, &mut quizzes);
+                    send_answer(&rt, &cli, &message, answer_res);
+                    continue;
+                }
+            }
             let cmd: Command = match message.text.parse() {
                 Ok(cmd) => cmd,
                 Err(e) => {
@@ -45,6 +70,7 @@ pub fn updates_processing(user_words: Arc<RwLock<UserWords>>, token: String) {
                         chat_id: message.chat.id,
                         text: format!("Can't parse command: {}", e),
                         reply_to_message_id: message.message_id,
+                        reply_markup: None,
                     }));
                     if let Err(e) = r {
                         error!("Can't send telegram error message: {}", e);
@@ -54,156 +80,590 @@ pub fn updates_processing(user_words: Arc<RwLock<UserWords>>, token: String) {
             };
             let answer_res = match cmd {
                 Command::AddLang(lang) => {
-                    let r = {
-                        let mut user_w = user_words.write().unwrap();
-                        user_w.add_lang(message.chat.id, &lang)
-                    };
+                    let r = user_words.add_lang(message.chat.id, &lang);
                     match r {
                         Ok(()) => list_langs_answer(user_words.clone(), &message),
                         Err(e) => Err(e),
                     }
                 }
-                Command::DeleteLang(lang) => {
-                    let r = {
-                        let mut user_w = user_words.write().unwrap();
-                        user_w.delete_lang(message.chat.id, &lang)
-                    };
+                Command::DeleteLang(lang, true) => {
+                    delete_lang_preview(user_words.clone(), &message, &lang)
+                }
+                Command::DeleteLang(lang, false) => {
+                    let r = user_words.delete_lang(message.chat.id, &lang, None);
                     match r {
                         Ok(()) => list_langs_answer(user_words.clone(), &message),
                         Err(e) => Err(e),
                     }
                 }
-                Command::AddWord(word) => {
-                    let r = {
-                        let mut user_w = user_words.write().unwrap();
-                        user_w.add_word(message.chat.id, &word)
-                    };
+                Command::AddWord(word, Some(lang)) => {
+                    let word = Word::new(&word, lang);
+                    let r = user_words.add_word(message.chat.id, &word);
                     match r {
                         Ok(()) => list_words_answer(user_words.clone(), &message, &word.word),
                         Err(e) => Err(e),
                     }
                 }
-                Command::DeleteWord(word) => {
-                    let mut user_w = user_words.write().unwrap();
-                    match user_w.delete_word(message.chat.id, &word) {
-                        Ok(()) => Ok(client::Answer::from_message("Word deleted", &message)),
+                Command::AddWord(word, None) => {
+                    detect_word_answer(user_words.clone(), &message, &word)
+                }
+                Command::ConfirmWord(text, word, lang) => {
+                    confirm_word_answer(user_words.clone(), &message, &text, &word, &lang)
+                }
+                Command::Import(lang, words) => {
+                    import_answer(user_words.clone(), &message, &lang, &words)
+                }
+                Command::DeleteWord(word, lang) => {
+                    delete_word_answer(user_words.clone(), &message, &word, lang)
+                }
+                Command::AddTranslation(word, tran) => {
+                    match user_words.add_translation(message.chat.id, &word, &tran) {
+                        Ok(trs) => Ok(entries_answer(&trs, &message)),
+                        Err(e) => Err(e),
+                    }
+                }
+                Command::DeleteTranslation(word, tran, lang) => {
+                    match user_words.delete_translation(
+                        message.chat.id,
+                        &word,
+                        &tran,
+                        lang.as_ref(),
+                    ) {
+                        Ok(trs) => Ok(entries_answer(&trs, &message)),
                         Err(e) => Err(e),
                     }
                 }
+                Command::OrderTranslations(word, order) => {
+                    match user_words.reorder_translations(message.chat.id, &word, &order) {
+                        Ok(trs) => Ok(entries_answer(&trs, &message)),
+                        Err(e) => Err(e),
+                    }
+                }
+                Command::EditWord(word, edit) => {
+                    match user_words.edit_word(message.chat.id, &word, &edit) {
+                        Ok(trs) => Ok(entries_answer(&trs, &message)),
+                        Err(e) => Err(e),
+                    }
+                }
+                Command::Undo(word) => undo_answer(user_words.clone(), &message, &word),
                 Command::ListWords(pattern) => {
                     list_words_answer(user_words.clone(), &message, &pattern)
                 }
                 Command::ListLangs => list_langs_answer(user_words.clone(), &message),
-                Command::ListRandomWords(n) => {
-                    let mut user_w = user_words.write().unwrap();
-                    match user_w.list_words(message.chat.id, None) {
-                        Ok(mut trs) => {
-                            let mut trs_s: Vec<String> = vec![];
-                            let mut words: Vec<Word> = vec![];
-                            let mut len = trs.len() / 2;
-                            trs.sort_by_key(|k| k.last_seen);
-                            if len < n as usize {
-                                len = n as usize;
-                            }
-                            let mut uniq: HashSet<usize> = HashSet::new();
-                            while trs_s.len() < n as usize {
-                                let s: usize = rand::thread_rng().gen_range(0..len);
-                                if uniq.contains(&s) {
-                                    continue;
-                                }
-                                uniq.insert(s);
-                                trs_s.push(format!("{}\n", &trs[s]));
-                                words.push(trs[s].word.clone())
-                            }
-                            if !words.is_empty() {
-                                if let Err(e) = user_w.update_last_seen(message.chat.id, words) {
-                                    error!("Can't update last seen for: {}. {}", message.chat.id, e)
-                                }
-                            }
-                            let mut msg = trs_s.concat();
-                            if msg == "" {
-                                msg = "No words".to_string()
-                            }
-                            Ok(client::Answer::from_message(&msg, &message))
-                        }
+                Command::ReviewWords(n) => review_card_answer(user_words.clone(), &message, n),
+                Command::GradeWord(word, grade) => {
+                    match user_words.review_word(message.chat.id, &word, None, grade) {
+                        Ok(()) => Ok(client::Answer::from_message("Word graded", &message)),
                         Err(e) => Err(e),
                     }
                 }
+                Command::Quiz => quiz_question(user_words.clone(), &message, &mut quizzes),
                 Command::Help => {
-                    let helps = vec![
+                    let helps = [
                         Command::ListLangs.help(),
                         Command::AddLang("en".parse().unwrap()).help(),
-                        Command::DeleteLang("en".parse().unwrap()).help(),
+                        Command::DeleteLang("en".parse().unwrap(), true).help(),
                         Command::ListWords("word".to_string()).help(),
-                        Command::ListRandomWords(0).help(),
-                        Command::AddWord(Word {
-                            word: "word".to_string(),
-                            lang: "en".parse().unwrap(),
-                        })
-                            .help(),
-                        Command::DeleteWord("".to_string()).help(),
+                        Command::ReviewWords(0).help(),
+                        Command::GradeWord("word".to_string(), Grade::Good).help(),
+                        Command::Quiz.help(),
+                        Command::AddWord("word".to_string(), None).help(),
+                        Command::Import("en".parse().unwrap(), vec![]).help(),
+                        Command::DeleteWord("".to_string(), None).help(),
+                        Command::AddTranslation(
+                            "".to_string(),
+                            Word {
+                                word: "".to_string(),
+                                lang: "en".parse().unwrap(),
+                                pos: None,
+                                manual: true,
+                            },
+                        )
+                        .help(),
+                        Command::DeleteTranslation("".to_string(), "".to_string(), None).help(),
+                        Command::OrderTranslations("".to_string(), vec![]).help(),
+                        Command::EditWord("".to_string(), Edit::Note(None)).help(),
+                        Command::EditWord("".to_string(), Edit::AddExample("".to_string())).help(),
+                        Command::EditWord("".to_string(), Edit::DeleteExample(0)).help(),
+                        Command::EditWord("".to_string(), Edit::Grammar(None)).help(),
+                        Command::EditWord("".to_string(), Edit::AddTags(vec![])).help(),
+                        Command::EditWord("".to_string(), Edit::DeleteTags(vec![])).help(),
+                        Command::Undo("".to_string()).help(),
                         Command::Help.help(),
                     ];
                     Ok(client::Answer::from_message(
                         format!("List of commands:\n{}", helps.join("\n")).as_str(),
-                            &message,
+                        &message,
                     ))
-                },
-            };
-            let answer = match answer_res {
-                Ok(answer) => answer,
-                Err(e) => {
-                    error!(
-                        "Can't process command from: {}. {}. {}",
-                        message.chat.id, message.text, e
-                    );
-                    let answer = client::Answer {
-                        chat_id: message.chat.id,
-                        text: format!("Can't process command: {}", e),
-                        reply_to_message_id: message.message_id,
-                    };
-                    answer
                 }
             };
-            if let Err(e) = rt.block_on(cli.send_msg(&answer)) {
-                error!(
-                    "Can't send telegram answer to: '{}'. {}. {}",
-                    message.text, answer, e
-                )
+            send_answer(&rt, &cli, &message, answer_res);
+        }
+    }
+}
+
+fn send_answer(
+    rt: &Runtime,
+    cli: &client::Client,
+    message: &client::Message,
+    answer_res: Result<client::Answer, Box<dyn error::Error>>,
+) {
+    let answer = match answer_res {
+        Ok(answer) => answer,
+        Err(e) => {
+            error!(
+                "Can't process command from: {}. {}. {}",
+                message.chat.id, message.text, e
+            );
+            client::Answer {
+                chat_id: message.chat.id,
+                text: format!("Can't process command: {}", e),
+                reply_to_message_id: message.message_id,
+                reply_markup: None,
             }
         }
+    };
+    if let Err(e) = rt.block_on(cli.send_msg(&answer)) {
+        error!(
+            "Can't send telegram answer to: '{}'. {}. {}",
+            message.text, answer, e
+        )
+    }
+}
+
+fn callback_processing(
+    rt: &Runtime,
+    cli: &client::Client,
+    user_words: Arc<UserWords>,
+    query: &client::CallbackQuery,
+) {
+    let text = match process_callback(rt, cli, user_words, query) {
+        Ok(()) => None,
+        Err(e) => {
+            error!(
+                "Can't process callback from: {}. {:?}. {}",
+                query.from.id, query.data, e
+            );
+            Some(format!("Can't process action: {}", e))
+        }
+    };
+    let answer = client::CallbackAnswer {
+        callback_query_id: query.id.to_string(),
+        text,
+    };
+    if let Err(e) = rt.block_on(cli.answer_callback(&answer)) {
+        error!("Can't answer telegram callback: {}. {}", query.id, e)
+    }
+}
+
+fn process_callback(
+    rt: &Runtime,
+    cli: &client::Client,
+    user_words: Arc<UserWords>,
+    query: &client::CallbackQuery,
+) -> Result<(), Box<dyn error::Error>> {
+    let message = match &query.message {
+        Some(msg) => msg,
+        None => {
+            warn!("Callback without message from: {}", query.from.id);
+            return Ok(());
+        }
+    };
+    if query.from.id != card_owner(message) {
+        return Err("Only the author of the command can use its buttons".into());
+    }
+    match query.data.as_deref().unwrap_or("").parse()? {
+        Callback::Review(cb) => review_callback(rt, cli, user_words, message, cb),
+        Callback::Delete(cb) => delete_callback(rt, cli, user_words, message, cb),
+        Callback::DeleteLang(cb) => delete_lang_callback(rt, cli, user_words, message, cb),
+        Callback::AddWord(cb) => add_word_callback(rt, cli, user_words, message, cb),
+    }
+}
+
+// Buttons are on the bot answer to a command, in a group chat only its author may press them.
+// Without the command the owner is the chat, which is the user in a private chat
+fn card_owner(message: &client::Message) -> i64 {
+    match &message.reply_to_message {
+        Some(command) => command.from.id,
+        None => message.chat.id,
+    }
+}
+
+// Long phrases come in callbacks as a hash, see callback::word_key
+fn callback_word(
+    user_words: Arc<UserWords>,
+    user_id: i64,
+    key: &str,
+) -> Result<String, Box<dyn error::Error>> {
+    if word_key(key) == key {
+        return Ok(key.to_string());
+    }
+    let words = user_words.list_words(user_id, None)?;
+
+    Ok(word_by_key(key, words.into_iter().map(|tr| tr.word.word))?)
+}
+
+// Grade the card, reveal it by editing the card message and send the next one
+fn review_callback(
+    rt: &Runtime,
+    cli: &client::Client,
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    cb: ReviewCallback,
+) -> Result<(), Box<dyn error::Error>> {
+    let word = callback_word(user_words.clone(), message.chat.id, &cb.word)?;
+    let tr = {
+        user_words.review_word(message.chat.id, &word, Some(&cb.lang), cb.grade)?;
+        user_words
+            .list_words(message.chat.id, None)?
+            .into_iter()
+            .find(|tr| tr.word.lang == cb.lang && same_word(&tr.word.word, &word))
+    };
+    let text = match tr {
+        Some(tr) => format!("{}Grade: {}", tr, cb.grade),
+        None => "Word not found".to_string(),
+    };
+    rt.block_on(cli.edit_msg(&client::EditMessage {
+        chat_id: message.chat.id,
+        message_id: message.message_id,
+        text,
+        reply_markup: None,
+    }))?;
+    if cb.remaining > 0 {
+        let mut answer = review_card_answer(user_words, message, cb.remaining)?;
+        // the next card answers the same command, so it has the same owner
+        if let Some(command) = &message.reply_to_message {
+            answer.reply_to_message_id = command.message_id;
+        }
+        rt.block_on(cli.send_msg(&answer))?;
     }
+
+    Ok(())
+}
+
+// Delete the chosen word and replace the choice with the result
+fn delete_callback(
+    rt: &Runtime,
+    cli: &client::Client,
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    cb: DeleteCallback,
+) -> Result<(), Box<dyn error::Error>> {
+    let word = callback_word(user_words.clone(), message.chat.id, &cb.word)?;
+    let text = match user_words.delete_word(message.chat.id, &word, Some(&cb.lang))? {
+        0 => "Word not found".to_string(),
+        _ => format!("Word deleted: {} {}. Undo: /u", word, cb.lang),
+    };
+    rt.block_on(cli.edit_msg(&client::EditMessage {
+        chat_id: message.chat.id,
+        message_id: message.message_id,
+        text,
+        reply_markup: None,
+    }))?;
+
+    Ok(())
+}
+
+// A sure guess of the language is added at once, otherwise the user chooses it
+fn detect_word_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    word: &str,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let lang = match user_words.detect_lang(message.chat.id, word)? {
+        Detected::Sure(lang) => lang,
+        Detected::Unsure(langs) => {
+            let mut answer =
+                client::Answer::from_message(&format!("Which language is {}?", word), message);
+            answer.reply_markup = Some(add_word_keyboard(word, &langs));
+            return Ok(answer);
+        }
+    };
+    user_words.add_word(message.chat.id, &Word::new(word, lang))?;
+
+    list_words_answer(user_words, message, word)
+}
+
+// "to be" is either "to" in Belarusian or the phrase, the phrase gets the detected languages
+fn confirm_word_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    text: &str,
+    word: &str,
+    lang: &Lang,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let langs = match user_words.detect_lang(message.chat.id, text)? {
+        Detected::Sure(lang) => vec![lang],
+        Detected::Unsure(langs) => langs,
+    };
+    let mut answer = client::Answer::from_message(
+        &format!("Add {} in {} or the phrase {}?", word, lang.name(), text),
+        message,
+    );
+    answer.reply_markup = Some(confirm_word_keyboard(text, word, lang, &langs));
+
+    Ok(answer)
+}
+
+// The word isn't saved yet, so a hashed phrase is taken from the command the choice answers
+fn add_word_callback(
+    rt: &Runtime,
+    cli: &client::Client,
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    cb: AddWordCallback,
+) -> Result<(), Box<dyn error::Error>> {
+    let words = match message.reply_to_message.as_ref().map(|m| m.text.parse()) {
+        Some(Ok(Command::AddWord(w, _))) => vec![w],
+        Some(Ok(Command::ConfirmWord(text, w, _))) => vec![text, w],
+        _ => vec![],
+    };
+    let word = word_by_key(&cb.word, words)?;
+    user_words.add_word(message.chat.id, &Word::new(&word, cb.lang))?;
+    let text = list_words_answer(user_words, message, &word)?.text;
+    rt.block_on(cli.edit_msg(&client::EditMessage {
+        chat_id: message.chat.id,
+        message_id: message.message_id,
+        text,
+        reply_markup: None,
+    }))?;
+
+    Ok(())
+}
+
+// Cascade delete is confirmed by the user after seeing what will be removed
+fn delete_lang_preview(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    lang: &Lang,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let (words, translations) = user_words.lang_usage(message.chat.id, lang)?;
+    let mut answer = client::Answer::from_message(
+        &format!(
+            "Delete {}: {} words in it and {} translations into it will be removed. \
+             Archived words can be restored with /u",
+            lang, words, translations
+        ),
+        message,
+    );
+    answer.reply_markup = Some(delete_lang_keyboard(lang));
+
+    Ok(answer)
+}
+
+fn delete_lang_callback(
+    rt: &Runtime,
+    cli: &client::Client,
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    cb: DeleteLangCallback,
+) -> Result<(), Box<dyn error::Error>> {
+    let text = match cb.cascade {
+        Some(cascade) => {
+            user_words.delete_lang(message.chat.id, &cb.lang, Some(cascade))?;
+            format!("Language deleted: {}", cb.lang)
+        }
+        None => "Canceled".to_string(),
+    };
+    rt.block_on(cli.edit_msg(&client::EditMessage {
+        chat_id: message.chat.id,
+        message_id: message.message_id,
+        text,
+        reply_markup: None,
+    }))?;
+
+    Ok(())
+}
+
+// Without a language the word is deleted only if there is one entry of it,
+// otherwise the user chooses the language
+fn delete_word_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    word: &str,
+    lang: Option<Lang>,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    if lang.is_none() {
+        let langs = user_words.word_langs(message.chat.id, word)?;
+        if langs.len() > 1 {
+            let mut answer =
+                client::Answer::from_message("Several words match, which one to delete?", message);
+            answer.reply_markup = Some(delete_keyboard(word, &langs));
+            return Ok(answer);
+        }
+    }
+    let text = match user_words.delete_word(message.chat.id, word, lang.as_ref())? {
+        0 => "Word not found",
+        _ => "Word deleted. Undo: /u",
+    };
+
+    Ok(client::Answer::from_message(text, message))
+}
+
+// Shows the front side of the next due word with grade buttons, n is the size of the review session
+fn review_card_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    n: i8,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let trs = user_words.due_words(message.chat.id, 1)?;
+    let tr = match trs.first() {
+        Some(tr) => tr,
+        None => return Ok(client::Answer::from_message("No words to review", message)),
+    };
+    if let Err(e) = user_words.update_last_seen(message.chat.id, vec![tr.word.clone()]) {
+        error!("Can't update last seen for: {}. {}", message.chat.id, e)
+    }
+    let mut answer = client::Answer::from_message(
+        &format!("{}\t{}", tr.word.lang.lang.to_uppercase(), tr.word.word),
+        message,
+    );
+    answer.reply_markup = Some(review_keyboard(&tr.word, n.max(1) - 1));
+
+    Ok(answer)
+}
+
+fn next_quiz_session(
+    user_words: Arc<UserWords>,
+    user_id: i64,
+) -> Result<Option<quiz::Session>, Box<dyn error::Error>> {
+    let due = user_words.due_words(user_id, usize::MAX)?;
+    if let Some(tr) = due.into_iter().find(|tr| !tr.translates.is_empty()) {
+        return Ok(Some(quiz::Session::new(tr)));
+    }
+    let trs: Vec<_> = user_words
+        .list_words(user_id, None)?
+        .into_iter()
+        .filter(|tr| !tr.translates.is_empty())
+        .collect();
+
+    Ok(trs
+        .choose(&mut rand::thread_rng())
+        .map(|tr| quiz::Session::new(tr.clone())))
+}
+
+fn quiz_question(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    quizzes: &mut HashMap<i64, quiz::Session>,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    match next_quiz_session(user_words, message.chat.id)? {
+        Some(session) => {
+            let answer = client::Answer::from_message(&session.question(), message);
+            quizzes.insert(message.chat.id, session);
+            Ok(answer)
+        }
+        None => Ok(client::Answer::from_message("No words for quiz", message)),
+    }
+}
+
+fn quiz_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    session: &quiz::Session,
+    quizzes: &mut HashMap<i64, quiz::Session>,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let correct = session.check(&message.text);
+    let grade = if correct { Grade::Good } else { Grade::Again };
+    user_words.review_word(
+        message.chat.id,
+        &session.translate.word.word,
+        Some(&session.translate.word.lang),
+        grade,
+    )?;
+    let mut msg = if correct {
+        format!("Correct!\n{}", session.expected())
+    } else {
+        format!("Incorrect. Expected:\n{}", session.expected())
+    };
+    let next = quiz_question(user_words, message, quizzes)?;
+    msg.push_str(format!("\n\n{}", next.text).as_str());
+
+    Ok(client::Answer::from_message(&msg, message))
+}
+
+fn import_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    lang: &Lang,
+    words: &[String],
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let added = user_words.add_words(message.chat.id, words, lang)?;
+    let failed: Vec<String> = added
+        .iter()
+        .filter_map(|a| a.error.as_ref().map(|e| format!("{}: {}\n", a.word, e)))
+        .collect();
+    let mut msg = format!(
+        "Added {} of {} words",
+        added.len() - failed.len(),
+        added.len()
+    );
+    if !failed.is_empty() {
+        msg = format!("{}\nFailed:\n{}", msg, failed.concat());
+    }
+
+    Ok(client::Answer::from_message(&msg, message))
+}
+
+// Entries of an edited word, nothing is changed if there is no such word
+fn entries_answer(trs: &[storage::Translate], message: &client::Message) -> client::Answer {
+    if trs.is_empty() {
+        return client::Answer::from_message("Word not found", message);
+    }
+    let trs_s: Vec<String> = trs.iter().map(|tr| format!("{}\n", tr)).collect();
+
+    client::Answer::from_message(&trs_s.concat(), message)
+}
+
+fn undo_answer(
+    user_words: Arc<UserWords>,
+    message: &client::Message,
+    word: &str,
+) -> Result<client::Answer, Box<dyn error::Error>> {
+    let word = if word.is_empty() { None } else { Some(word) };
+    let trs = user_words.restore_word(message.chat.id, word)?;
+    if trs.is_empty() {
+        return Ok(client::Answer::from_message("Nothing to restore", message));
+    }
+    let trs_s: Vec<String> = trs.iter().map(|tr| format!("{}\n", tr)).collect();
+
+    Ok(client::Answer::from_message(
+        format!("Restored:\n{}", trs_s.concat()).as_str(),
+        message,
+    ))
 }
 
 fn list_words_answer(
-    user_words: Arc<RwLock<UserWords>>,
+    user_words: Arc<UserWords>,
     message: &client::Message,
     pattern: &str,
 ) -> Result<client::Answer, Box<dyn std::error::Error>> {
-    let user_w = user_words.read().unwrap();
-    match user_w.list_words(message.chat.id, Some(pattern)) {
+    match user_words.list_words(message.chat.id, Some(pattern)) {
         Ok(trs) => {
             let trs_s: Vec<String> = trs.iter().map(|tr| format!("{}\n", tr)).collect();
             let mut msg = trs_s.concat();
-            if msg == "" {
+            if msg.is_empty() {
                 msg = "No words".to_string()
             }
-            Ok(client::Answer::from_message(&msg, &message))
+            Ok(client::Answer::from_message(&msg, message))
         }
         Err(e) => Err(e),
     }
 }
 
 fn list_langs_answer(
-    user_words: Arc<RwLock<UserWords>>,
+    user_words: Arc<UserWords>,
     message: &client::Message,
 ) -> Result<client::Answer, Box<dyn std::error::Error>> {
-    let user_w = user_words.read().unwrap();
-    let langs = user_w.list_langs(message.chat.id)?;
-    let langs_s: Vec<String> = langs.iter().map(|l| format!(" {} ", l.lang)).collect();
+    let langs = user_words.list_langs(message.chat.id)?;
+    let langs_s: Vec<String> = langs
+        .iter()
+        .map(|l| format!("{} ({})\n", l.lang, l.name()))
+        .collect();
     let mut msg = langs_s.concat();
-    if msg == "" {
-        msg = "No langs".to_string()
+    if msg.is_empty() {
+        msg = "No langs\n".to_string()
     }
-    Ok(client::Answer::from_message(&msg, &message))
+    msg = format!("{}\nAvailable: {}", msg, langs::available().join(", "));
+    Ok(client::Answer::from_message(&msg, message))
 }
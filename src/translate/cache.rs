@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::error;
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::storage::write_atomic;
+use crate::translate::{Detection, Lang, Provider, Sense, Translate, Word};
+
+use async_trait::async_trait;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+// word, source lang, target lang, provider
+type Key = (String, String, String, String);
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Record {
+    word: String,
+    from: String,
+    to: String,
+    provider: String,
+    senses: Vec<Sense>,
+    created: u64,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+// Translations shared by all users and kept in a JSON file between restarts.
+// Entries older than ttl are asked again, the oldest ones are dropped above max_entries
+pub struct Store {
+    path: Option<String>,
+    ttl: u64,
+    max_entries: usize,
+    entries: Mutex<HashMap<Key, Record>>,
+    dirty: AtomicBool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Store {
+    // path None keeps the cache only in memory
+    pub fn open(
+        path: Option<&str>,
+        ttl: u64,
+        max_entries: usize,
+    ) -> Result<Store, Box<dyn error::Error>> {
+        let records: Vec<Record> = match path {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(data) => serde_json::from_str(&data)?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+                Err(e) => return Err(Box::new(e)),
+            },
+            None => vec![],
+        };
+        let store = Store {
+            path: path.map(|p| p.to_string()),
+            ttl,
+            max_entries,
+            entries: Mutex::new(HashMap::new()),
+            dirty: AtomicBool::new(false),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        };
+        {
+            let now = unix_now();
+            let mut entries = store.entries.lock().unwrap();
+            for r in records {
+                if r.created + ttl > now {
+                    entries.insert(key(&r.word, &r.from, &r.to, &r.provider), r);
+                }
+            }
+        }
+
+        Ok(store)
+    }
+
+    fn get(&self, key: &Key, now: u64) -> Option<Vec<Sense>> {
+        let entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(r) if r.created + self.ttl > now => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(r.senses.clone())
+            }
+            _ => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    fn put(&self, key: Key, senses: Vec<Sense>, now: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        let (word, from, to, provider) = key.clone();
+        entries.insert(
+            key,
+            Record {
+                word,
+                from,
+                to,
+                provider,
+                senses,
+                created: now,
+            },
+        );
+        if entries.len() > self.max_entries {
+            let mut created: Vec<(u64, Key)> = entries
+                .iter()
+                .map(|(k, r)| (r.created, k.clone()))
+                .collect();
+            created.sort();
+            // drop a tenth at once to not sort on every insert
+            let extra = entries.len() - self.max_entries + self.max_entries / 10;
+            for (_, k) in created.into_iter().take(extra) {
+                entries.remove(&k);
+            }
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+
+    // Writes the cache file if something has been added since the last save
+    pub fn save(&self) -> Result<(), Box<dyn error::Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let records: Vec<Record> = self.entries.lock().unwrap().values().cloned().collect();
+        if let Err(e) = write_atomic(path, serde_json::to_string(&records)?.as_bytes()) {
+            self.dirty.store(true, Ordering::Relaxed);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+fn key(word: &str, from: &str, to: &str, provider: &str) -> Key {
+    (
+        word.to_string(),
+        from.to_string(),
+        to.to_string(),
+        provider.to_string(),
+    )
+}
+
+pub fn save_loop(store: Arc<Store>, interval: Duration) {
+    loop {
+        sleep(interval);
+        if let Err(e) = store.save() {
+            error!("Can't save translate cache: {}", e);
+        }
+        let stats = store.stats();
+        info!(
+            "Translate cache: {} hits, {} misses, {} entries",
+            stats.hits, stats.misses, stats.entries
+        );
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// A provider behind the shared cache
+pub struct Cache {
+    store: Arc<Store>,
+    provider: Provider,
+    client: Box<dyn Translate + Send + Sync>,
+}
+
+impl Cache {
+    pub fn new(
+        store: Arc<Store>,
+        provider: Provider,
+        client: Box<dyn Translate + Send + Sync>,
+    ) -> Cache {
+        Cache {
+            store,
+            provider,
+            client,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Translate for Cache {
+    async fn translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        Ok(self
+            .senses(word, to)
+            .await?
+            .into_iter()
+            .map(|s| s.word)
+            .collect())
+    }
+
+    async fn senses(&self, word: &Word, to: &Lang) -> Result<Vec<Sense>, Box<dyn error::Error>> {
+        let now = unix_now();
+        let k = key(
+            &word.word,
+            &word.lang.lang,
+            &to.lang,
+            &self.provider.to_string(),
+        );
+        if let Some(senses) = self.store.get(&k, now) {
+            return Ok(senses);
+        }
+        let senses = self.client.senses(word, to).await?;
+        // a provider may learn the word later
+        if !senses.is_empty() {
+            self.store.put(k, senses.clone(), now);
+        }
+
+        Ok(senses)
+    }
+
+    // only words missing in the cache go to the provider, in one batch
+    async fn senses_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<Vec<Sense>>, Box<dyn error::Error>> {
+        let now = unix_now();
+        let provider = self.provider.to_string();
+        let keys: Vec<Key> = words
+            .iter()
+            .map(|w| key(w, &from.lang, &to.lang, &provider))
+            .collect();
+        let mut res: Vec<Option<Vec<Sense>>> =
+            keys.iter().map(|k| self.store.get(k, now)).collect();
+        let missed: Vec<String> = words
+            .iter()
+            .zip(res.iter())
+            .filter(|(_, r)| r.is_none())
+            .map(|(w, _)| w.to_string())
+            .collect();
+        if !missed.is_empty() {
+            let mut found = self
+                .client
+                .senses_batch(&missed, from, to)
+                .await?
+                .into_iter();
+            for (i, k) in keys.into_iter().enumerate() {
+                if res[i].is_some() {
+                    continue;
+                }
+                let senses = found.next().unwrap_or_default();
+                if !senses.is_empty() {
+                    self.store.put(k, senses.clone(), now);
+                }
+                res[i] = Some(senses);
+            }
+        }
+
+        Ok(res.into_iter().map(|r| r.unwrap_or_default()).collect())
+    }
+
+    // detection isn't cached, it is rare and depends on the user's languages
+    async fn detect(
+        &self,
+        text: &str,
+        langs: &[Lang],
+    ) -> Result<Vec<Detection>, Box<dyn error::Error>> {
+        self.client.detect(text, langs).await
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.client.supported_langs().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::error;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    use crate::translate::cache::{key, unix_now, Cache, Stats, Store};
+    use crate::translate::{http, Lang, Provider, Sense, Translate, Word};
+
+    use async_trait::async_trait;
+
+    // remembers words asked from the provider
+    struct Fake {
+        asked: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Translate for Fake {
+        async fn translate(
+            &self,
+            word: &Word,
+            to: &Lang,
+        ) -> Result<Vec<String>, Box<dyn error::Error>> {
+            self.asked.lock().unwrap().push(word.word.to_string());
+            Ok(vec![format!("{} {}", word.word, to)])
+        }
+
+        async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+            Ok(vec![])
+        }
+    }
+
+    fn senses(word: &str) -> Vec<Sense> {
+        vec![Sense {
+            word: word.to_string(),
+            pos: None,
+        }]
+    }
+
+    #[test]
+    fn ttl_and_size() {
+        let store = Store::open(None, 100, 10).unwrap();
+        let door = key("door", "en", "ru", "google");
+        assert_eq!(None, store.get(&door, 0));
+        store.put(door.clone(), senses("дверь"), 0);
+        assert_eq!(Some(senses("дверь")), store.get(&door, 99));
+        assert_eq!(None, store.get(&door, 100));
+        // another provider has its own entry
+        assert_eq!(None, store.get(&key("door", "en", "ru", "deepl"), 1));
+        assert_eq!(
+            Stats {
+                hits: 1,
+                misses: 3,
+                entries: 1
+            },
+            store.stats()
+        );
+
+        for n in 1..=10 {
+            store.put(
+                key(&format!("w{}", n), "en", "ru", "google"),
+                senses("w"),
+                n,
+            );
+        }
+        // the oldest are dropped
+        assert_eq!(9, store.stats().entries);
+        assert_eq!(None, store.get(&door, 10));
+        assert_eq!(None, store.get(&key("w1", "en", "ru", "google"), 10));
+        assert!(store.get(&key("w10", "en", "ru", "google"), 10).is_some());
+    }
+
+    #[test]
+    fn batch_asks_only_missed() {
+        let store = Arc::new(Store::open(None, 1000, 10).unwrap());
+        let asked = Arc::new(Mutex::new(vec![]));
+        let fake = Fake {
+            asked: asked.clone(),
+        };
+        let cache = Cache::new(store.clone(), Provider::Google, Box::new(fake));
+        let (en, ru): (Lang, Lang) = ("en".parse().unwrap(), "ru".parse().unwrap());
+        store.put(
+            key("door", "en", "ru", "google"),
+            senses("дверь"),
+            unix_now(),
+        );
+
+        let words = vec!["window".to_string(), "door".to_string()];
+        let res = http::block_on(cache.senses_batch(&words, &en, &ru)).unwrap();
+        assert_eq!(vec![senses("window ru"), senses("дверь")], res);
+        assert_eq!(vec!["window"], *asked.lock().unwrap());
+        http::block_on(cache.senses_batch(&words, &en, &ru)).unwrap();
+        assert_eq!(1, asked.lock().unwrap().len());
+    }
+
+    #[test]
+    fn case_kept() {
+        let store = Arc::new(Store::open(None, 1000, 10).unwrap());
+        let asked = Arc::new(Mutex::new(vec![]));
+        let fake = Fake {
+            asked: asked.clone(),
+        };
+        let cache = Cache::new(store.clone(), Provider::Google, Box::new(fake));
+        let (de, ru): (Lang, Lang) = ("de".parse().unwrap(), "ru".parse().unwrap());
+
+        // "Essen" is food and "essen" is to eat, they are asked and cached apart
+        let words = vec!["Essen".to_string(), "essen".to_string()];
+        let res = http::block_on(cache.senses_batch(&words, &de, &ru)).unwrap();
+        assert_eq!(vec![senses("Essen ru"), senses("essen ru")], res);
+        assert_eq!(vec!["Essen", "essen"], *asked.lock().unwrap());
+        assert_eq!(2, store.stats().entries);
+        assert_ne!(
+            key("Essen", "de", "ru", "google"),
+            key("essen", "de", "ru", "google")
+        );
+    }
+
+    #[test]
+    fn persist() {
+        let path = env::temp_dir().join(format!("lengwurds_cache_test_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        let now = unix_now();
+        let store = Store::open(Some(path), 1000, 10).unwrap();
+        store.put(key("door", "en", "ru", "google"), senses("дверь"), now);
+        store.put(
+            key("old", "en", "ru", "google"),
+            senses("старый"),
+            now - 2000,
+        );
+        store.save().unwrap();
+
+        let store = Store::open(Some(path), 1000, 10).unwrap();
+        assert_eq!(1, store.stats().entries);
+        assert_eq!(
+            Some(senses("дверь")),
+            store.get(&key("door", "en", "ru", "google"), now)
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
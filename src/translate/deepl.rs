@@ -0,0 +1,174 @@
+use std::error;
+
+use crate::translate::{http, Lang, Translate, Word};
+
+use async_trait::async_trait;
+use hyper;
+use serde::{Deserialize, Serialize};
+
+const API_URL: &str = "https://api.deepl.com";
+// keys of the free plan end with ":fx" and work only with this host
+const FREE_API_URL: &str = "https://api-free.deepl.com";
+
+#[derive(Serialize)]
+struct Query {
+    text: String,
+    source_lang: String,
+    target_lang: String,
+}
+
+#[derive(Deserialize)]
+struct TranslatesResponse {
+    pub translations: Vec<TranslationResponse>,
+}
+
+#[derive(Deserialize)]
+struct TranslationResponse {
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct SupportedLang {
+    pub language: String,
+}
+
+pub struct Client {
+    token: String,
+    url: String,
+}
+
+impl Client {
+    // url overrides the API host, by default it is chosen by the key
+    pub fn new(token: &str, url: Option<&str>) -> Client {
+        let url = match url {
+            Some(url) => url,
+            None if token.ends_with(":fx") => FREE_API_URL,
+            None => API_URL,
+        };
+        Client {
+            token: token.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn request(
+        &self,
+        method: hyper::Method,
+        path: &str,
+        body: hyper::Body,
+    ) -> Result<hyper::Request<hyper::Body>, Box<dyn error::Error>> {
+        Ok(hyper::Request::builder()
+            .method(method)
+            .uri(format!("{}{}", self.url, path))
+            .header("Authorization", format!("DeepL-Auth-Key {}", self.token))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)?)
+    }
+}
+
+// DeepL codes are upper case and English as a target needs a variant
+fn target_code(lang: &Lang) -> String {
+    match lang.lang.as_str() {
+        "en" => "EN-US".to_string(),
+        "pt" => "PT-BR".to_string(),
+        l => l.to_uppercase(),
+    }
+}
+
+// "EN-GB" and "EN-US" are both "en" for us
+fn lang_code(code: &str) -> String {
+    code.split('-').next().unwrap_or(code).to_lowercase()
+}
+
+impl Client {
+    pub async fn async_translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let q = Query {
+            text: word.word.to_string(),
+            source_lang: word.lang.lang.to_uppercase(),
+            target_lang: target_code(to),
+        };
+        let body = hyper::Body::from(serde_qs::to_string(&q)?);
+        let req = self.request(hyper::Method::POST, "/v2/translate", body)?;
+        let res: TranslatesResponse = http::send(req).await?;
+
+        Ok(res.translations.into_iter().map(|t| t.text).collect())
+    }
+
+    pub async fn async_supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let req = self.request(
+            hyper::Method::GET,
+            "/v2/languages?type=target",
+            hyper::Body::empty(),
+        )?;
+        let res: Vec<SupportedLang> = http::send(req).await?;
+        let mut langs: Vec<String> = vec![];
+        for l in res {
+            let code = lang_code(&l.language);
+            if !langs.contains(&code) {
+                langs.push(code)
+            }
+        }
+
+        Ok(langs)
+    }
+}
+
+#[async_trait(?Send)]
+impl Translate for Client {
+    async fn translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_translate(word, to).await
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_supported_langs().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::deepl::{lang_code, target_code, Client, Query, TranslatesResponse};
+
+    #[test]
+    fn codes() {
+        assert_eq!("EN-US", target_code(&"en".parse().unwrap()));
+        assert_eq!("DE", target_code(&"de".parse().unwrap()));
+        assert_eq!("en", lang_code("EN-GB"));
+        assert_eq!("de", lang_code("DE"));
+
+        assert_eq!(
+            "https://api-free.deepl.com",
+            Client::new("key:fx", None).url
+        );
+        assert_eq!("https://api.deepl.com", Client::new("key", None).url);
+        assert_eq!(
+            "http://localhost:8080",
+            Client::new("key", Some("http://localhost:8080/")).url
+        );
+    }
+
+    #[test]
+    fn query_and_response() {
+        let q = Query {
+            text: "die Tür".to_string(),
+            source_lang: "DE".to_string(),
+            target_lang: "EN-US".to_string(),
+        };
+        assert_eq!(
+            "text=die+T%C3%BCr&source_lang=DE&target_lang=EN-US",
+            serde_qs::to_string(&q).unwrap()
+        );
+        let res: TranslatesResponse = serde_json::from_str(
+            r#"{"translations": [{"detected_source_language": "DE", "text": "the door"}]}"#,
+        )
+        .unwrap();
+        assert_eq!("the door", res.translations[0].text);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::translate::{Detection, Lang};
+
+use whatlang::Detector;
+
+// whatlang knows languages by ISO 639-3 codes
+const CODES: [(&str, whatlang::Lang); 31] = [
+    ("ar", whatlang::Lang::Ara),
+    ("az", whatlang::Lang::Aze),
+    ("be", whatlang::Lang::Bel),
+    ("bg", whatlang::Lang::Bul),
+    ("cs", whatlang::Lang::Ces),
+    ("da", whatlang::Lang::Dan),
+    ("de", whatlang::Lang::Deu),
+    ("el", whatlang::Lang::Ell),
+    ("en", whatlang::Lang::Eng),
+    ("es", whatlang::Lang::Spa),
+    ("et", whatlang::Lang::Est),
+    ("fa", whatlang::Lang::Pes),
+    ("fi", whatlang::Lang::Fin),
+    ("fr", whatlang::Lang::Fra),
+    ("he", whatlang::Lang::Heb),
+    ("hi", whatlang::Lang::Hin),
+    ("hu", whatlang::Lang::Hun),
+    ("hy", whatlang::Lang::Hye),
+    ("it", whatlang::Lang::Ita),
+    ("ja", whatlang::Lang::Jpn),
+    ("ka", whatlang::Lang::Kat),
+    ("ko", whatlang::Lang::Kor),
+    ("lt", whatlang::Lang::Lit),
+    ("lv", whatlang::Lang::Lav),
+    ("nl", whatlang::Lang::Nld),
+    ("no", whatlang::Lang::Nob),
+    ("pl", whatlang::Lang::Pol),
+    ("pt", whatlang::Lang::Por),
+    ("ro", whatlang::Lang::Ron),
+    ("ru", whatlang::Lang::Rus),
+    ("sv", whatlang::Lang::Swe),
+];
+
+// The most likely of the given languages by the text itself, without a provider.
+// Empty if none of the languages is known to the detector
+pub fn local(text: &str, langs: &[Lang]) -> Vec<Detection> {
+    let allowed: Vec<whatlang::Lang> = langs
+        .iter()
+        .filter_map(|l| CODES.iter().find(|(c, _)| *c == l.lang))
+        .map(|(_, w)| *w)
+        .collect();
+    if allowed.is_empty() {
+        return vec![];
+    }
+    let info = match Detector::with_allowlist(allowed).detect(text) {
+        Some(info) => info,
+        None => return vec![],
+    };
+
+    CODES
+        .iter()
+        .filter(|(_, w)| *w == info.lang())
+        .map(|(c, _)| Detection {
+            lang: Lang {
+                lang: c.to_string(),
+            },
+            confidence: info.confidence(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::detect::local;
+    use crate::translate::Lang;
+
+    #[test]
+    fn detect_local() {
+        let langs: Vec<Lang> = ["en", "ru", "de", "kk"]
+            .iter()
+            .map(|l| l.parse().unwrap())
+            .collect();
+        for (text, lang) in [
+            ("дверь", "ru"),
+            ("the door is open", "en"),
+            ("auf Wiedersehen", "de"),
+            ("Haus", "de"),
+        ] {
+            let res = local(text, &langs);
+            assert_eq!(lang, res[0].lang.lang, "{}", text);
+        }
+        // the script alone tells Russian from the rest, one short word doesn't
+        assert_eq!(1.0, local("дверь", &langs)[0].confidence);
+        assert!(local("Haus", &langs)[0].confidence < 0.5);
+
+        let en_kk: Vec<Lang> = vec!["en".parse().unwrap(), "kk".parse().unwrap()];
+        assert!(local("сөз", &en_kk).is_empty());
+        assert!(local("door", &en_kk[1..]).is_empty());
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::OnceLock;
+
+use crate::translate::{langs, Lang, Sense, Translate, Word};
+
+use async_trait::async_trait;
+use flate2::read::MultiGzDecoder;
+use log::{info, warn};
+use regex::Regex;
+use serde::Deserialize;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Error {
+    description: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Dictionary: {}", &self.description)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+fn error(description: String) -> Box<dyn error::Error> {
+    Box::new(Error { description })
+}
+
+// A line of a kaikki.org (Wiktextract) JSONL dump
+#[derive(Deserialize)]
+struct WiktEntry {
+    word: String,
+    lang_code: String,
+    #[serde(default)]
+    pos: Option<String>,
+    #[serde(default)]
+    senses: Vec<WiktSense>,
+    #[serde(default)]
+    translations: Vec<WiktTranslation>,
+}
+
+#[derive(Deserialize)]
+struct WiktSense {
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct WiktTranslation {
+    // older dumps use "code", newer ones "lang_code"
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    lang_code: Option<String>,
+    // notes like "please add this translation" have no word
+    #[serde(default)]
+    word: Option<String>,
+}
+
+// Translations from local StarDict and Wiktextract files, works without a key and network
+pub struct Dictionary {
+    // (from, lowercase word, to) -> senses
+    index: HashMap<(String, String, String), Vec<Sense>>,
+}
+
+impl Dictionary {
+    pub fn new() -> Dictionary {
+        Dictionary {
+            index: HashMap::new(),
+        }
+    }
+
+    // specs are "wiktextract:path.jsonl" or "stardict:from-to:path.ifo"
+    pub fn load(specs: &[String]) -> Result<Dictionary, Box<dyn error::Error>> {
+        if specs.is_empty() {
+            return Err(error("no dictionary files".to_string()));
+        }
+        let mut dict = Dictionary::new();
+        for spec in specs {
+            let parts: Vec<&str> = spec.splitn(3, ':').collect();
+            match parts[..] {
+                ["wiktextract", path] => dict.load_wiktextract(path)?,
+                ["stardict", langs, path] => match langs.split_once('-') {
+                    Some((from, to)) => dict.load_stardict(path, from, to)?,
+                    None => return Err(error(format!("invalid languages in {}", spec))),
+                },
+                _ => return Err(error(format!("invalid dictionary {}", spec))),
+            }
+        }
+        info!("Dictionary loaded, {} words", dict.index.len());
+
+        Ok(dict)
+    }
+
+    fn add(&mut self, from: &str, word: &str, to: &str, sense: Sense) {
+        // only languages of the static table, dumps have hundreds of them.
+        // The provider list isn't used, it may be filled after the dictionary is loaded
+        let (from, to) = (langs::normalize(from), langs::normalize(to));
+        if from == to || !langs::known(&from) || !langs::known(&to) {
+            return;
+        }
+        let senses = self
+            .index
+            .entry((from, word.to_lowercase(), to))
+            .or_default();
+        if !senses.contains(&sense) {
+            senses.push(sense)
+        }
+    }
+
+    fn load_wiktextract(&mut self, path: &str) -> Result<(), Box<dyn error::Error>> {
+        let f = BufReader::new(fs::File::open(path)?);
+        for (n, line) in f.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Err(e) = self.add_wiktextract(&line) {
+                warn!("{}:{} skipped: {}", path, n + 1, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_wiktextract(&mut self, line: &str) -> Result<(), Box<dyn error::Error>> {
+        let entry: WiktEntry = serde_json::from_str(line)?;
+        let from = entry.lang_code.as_str();
+        for tr in entry.translations {
+            let to = match tr.code.or(tr.lang_code) {
+                Some(to) => to,
+                None => continue,
+            };
+            if let Some(word) = tr.word {
+                let sense = Sense {
+                    word,
+                    pos: entry.pos.clone(),
+                };
+                self.add(from, &entry.word, &to, sense);
+            }
+        }
+        // glosses of non-English entries are English translations
+        if from != "en" {
+            for s in entry.senses {
+                for gloss in s.glosses {
+                    let sense = Sense {
+                        word: gloss,
+                        pos: entry.pos.clone(),
+                    };
+                    self.add(from, &entry.word, "en", sense);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_stardict(
+        &mut self,
+        ifo: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let info = parse_ifo(&fs::read_to_string(ifo)?)?;
+        let base = ifo.trim_end_matches(".ifo");
+        let idx = fs::read(format!("{}.idx", base))?;
+        let dict = match fs::read(format!("{}.dict", base)) {
+            Ok(dict) => dict,
+            Err(_) => {
+                let mut dict = vec![];
+                MultiGzDecoder::new(fs::File::open(format!("{}.dict.dz", base))?)
+                    .read_to_end(&mut dict)?;
+                dict
+            }
+        };
+        let bits64 = info.get("idxoffsetbits").map(|b| b.as_str()) == Some("64");
+        let types = info.get("sametypesequence").map(|t| t.as_str());
+        for (word, offset, size) in parse_idx(&idx, bits64)? {
+            let (start, end) = (offset as usize, offset as usize + size as usize);
+            if end > dict.len() {
+                return Err(error(format!("{} is out of {}.dict", word, base)));
+            }
+            for sense in definition_senses(&word, &definition_text(&dict[start..end], types)) {
+                self.add(from, &word, to, sense);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Dictionary::new()
+    }
+}
+
+// key=value lines after the "StarDict's dict ifo file" magic
+fn parse_ifo(text: &str) -> Result<HashMap<String, String>, Box<dyn error::Error>> {
+    let mut lines = text.lines();
+    if lines.next().map(|l| l.trim()) != Some("StarDict's dict ifo file") {
+        return Err(error("not a StarDict .ifo file".to_string()));
+    }
+
+    Ok(lines
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect())
+}
+
+// word, offset and size of its article in .dict
+type IdxEntry = (String, u64, u32);
+
+// .idx entries are a NUL terminated word, big-endian offset and size in .dict
+fn parse_idx(data: &[u8], bits64: bool) -> Result<Vec<IdxEntry>, Box<dyn error::Error>> {
+    let offset_len = if bits64 { 8 } else { 4 };
+    let mut res = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        let end = match data[pos..].iter().position(|&b| b == 0) {
+            Some(n) => pos + n,
+            None => return Err(error("truncated .idx word".to_string())),
+        };
+        let word = String::from_utf8_lossy(&data[pos..end]).to_string();
+        pos = end + 1;
+        if pos + offset_len + 4 > data.len() {
+            return Err(error(format!("truncated .idx entry {}", word)));
+        }
+        let mut offset: u64 = 0;
+        for b in &data[pos..pos + offset_len] {
+            offset = offset << 8 | *b as u64;
+        }
+        pos += offset_len;
+        let size = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        pos += 4;
+        res.push((word, offset, size));
+    }
+
+    Ok(res)
+}
+
+// Text of a .dict article. With sametypesequence the article is bare data,
+// otherwise every field starts with its type and text fields end with NUL
+fn definition_text(data: &[u8], types: Option<&str>) -> String {
+    if types.is_some() {
+        return String::from_utf8_lossy(data).to_string();
+    }
+    let mut text = vec![];
+    let mut pos = 0;
+    while pos < data.len() {
+        let kind = data[pos];
+        pos += 1;
+        if kind.is_ascii_uppercase() {
+            // binary field with a 32 bit size, no text in it
+            if pos + 4 > data.len() {
+                break;
+            }
+            let size = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+            pos += 4 + size as usize;
+            continue;
+        }
+        let end = data[pos..]
+            .iter()
+            .position(|&b| b == 0)
+            .map_or(data.len(), |n| pos + n);
+        text.push(String::from_utf8_lossy(&data[pos..end]).to_string());
+        pos = end + 1;
+    }
+
+    text.join("\n")
+}
+
+// Common part of speech marks of en-ru dictionaries
+const POS_MARKS: [(&str, &str); 14] = [
+    ("n", "noun"),
+    ("noun", "noun"),
+    ("сущ", "noun"),
+    ("v", "verb"),
+    ("verb", "verb"),
+    ("гл", "verb"),
+    ("adj", "adj"),
+    ("a", "adj"),
+    ("прил", "adj"),
+    ("adv", "adv"),
+    ("нареч", "adv"),
+    ("prep", "prep"),
+    ("pron", "pron"),
+    ("conj", "conj"),
+];
+
+// Compiled once, a full StarDict dump has hundreds of thousands of articles
+static TAGS: OnceLock<Regex> = OnceLock::new();
+static NUMBERING: OnceLock<Regex> = OnceLock::new();
+
+fn pos_mark(s: &str) -> Option<&'static str> {
+    let s = s.trim().trim_end_matches('.').to_lowercase();
+    POS_MARKS.iter().find(|(m, _)| *m == s).map(|(_, p)| *p)
+}
+
+// Splits an article into senses: one per line or ";" separated item,
+// lines with a part of speech mark apply it to the following senses
+fn definition_senses(word: &str, text: &str) -> Vec<Sense> {
+    let tags = TAGS.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+    let numbering = NUMBERING.get_or_init(|| Regex::new(r"^(\d+|[a-zа-я])[.)]\s*").unwrap());
+    let mut pos: Option<String> = None;
+    let mut res: Vec<Sense> = vec![];
+    for line in tags.replace_all(text, "\n").lines() {
+        let mut line = line.trim();
+        // transcription and the headword repeated in the article
+        if line.is_empty() || line.starts_with('[') || line.to_lowercase() == word.to_lowercase() {
+            continue;
+        }
+        if let Some(p) = pos_mark(line) {
+            pos = Some(p.to_string());
+            continue;
+        }
+        line = numbering.find(line).map_or(line, |m| &line[m.end()..]);
+        for item in line.split(';') {
+            let word = item.trim().trim_end_matches('.').trim();
+            if word.is_empty() {
+                continue;
+            }
+            let sense = Sense {
+                word: word.to_string(),
+                pos: pos.clone(),
+            };
+            if !res.contains(&sense) {
+                res.push(sense)
+            }
+        }
+    }
+
+    res
+}
+
+#[async_trait(?Send)]
+impl Translate for Dictionary {
+    async fn translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        Ok(self
+            .senses(word, to)
+            .await?
+            .into_iter()
+            .map(|s| s.word)
+            .collect())
+    }
+
+    async fn senses(&self, word: &Word, to: &Lang) -> Result<Vec<Sense>, Box<dyn error::Error>> {
+        let key = (
+            word.lang.lang.to_string(),
+            word.word.to_lowercase(),
+            to.lang.to_string(),
+        );
+
+        Ok(self.index.get(&key).cloned().unwrap_or_default())
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let mut langs: Vec<String> = vec![];
+        for (from, _, to) in self.index.keys() {
+            for l in [from, to] {
+                if !langs.contains(l) {
+                    langs.push(l.to_string())
+                }
+            }
+        }
+        langs.sort();
+
+        Ok(langs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::dictionary::{
+        definition_senses, definition_text, parse_idx, parse_ifo, Dictionary,
+    };
+    use crate::translate::{http, Sense, Translate, Word};
+
+    fn sense(word: &str, pos: Option<&str>) -> Sense {
+        Sense {
+            word: word.to_string(),
+            pos: pos.map(|p| p.to_string()),
+        }
+    }
+
+    #[test]
+    fn wiktextract() {
+        let mut dict = Dictionary::new();
+        dict.add_wiktextract(
+            r#"{"word": "door", "lang_code": "en", "pos": "noun", "senses": [{"glosses": ["A portal"]}],
+                "translations": [{"code": "ru", "word": "дверь"}, {"lang_code": "de", "word": "Tür"},
+                {"code": "ru", "note": "please add"}, {"code": "fr", "word": "porte"}]}"#,
+        )
+        .unwrap();
+        dict.add_wiktextract(
+            r#"{"word": "Tür", "lang_code": "de", "pos": "noun", "senses": [{"glosses": ["door"]}, {"glosses": ["gate"]}]}"#,
+        )
+        .unwrap();
+        assert!(dict.add_wiktextract("{}").is_err());
+
+        let door = Word::new("Door", "en".parse().unwrap());
+        assert_eq!(
+            vec![sense("дверь", Some("noun"))],
+            http::block_on(dict.senses(&door, &"ru".parse().unwrap())).unwrap()
+        );
+        assert!(http::block_on(dict.senses(&door, &"kk".parse().unwrap()))
+            .unwrap()
+            .is_empty());
+        let tur = Word::new("tür", "de".parse().unwrap());
+        assert_eq!(
+            vec!["door", "gate"],
+            http::block_on(dict.translate(&tur, &"en".parse().unwrap())).unwrap()
+        );
+        assert_eq!(
+            vec!["de", "en", "fr", "ru"],
+            http::block_on(dict.supported_langs()).unwrap()
+        );
+    }
+
+    #[test]
+    fn stardict() {
+        let ifo = parse_ifo("StarDict's dict ifo file\nversion=2.4.2\nidxoffsetbits=64\n").unwrap();
+        assert_eq!("64", ifo["idxoffsetbits"]);
+        assert!(parse_ifo("version=2.4.2").is_err());
+
+        let mut idx = b"door\0".to_vec();
+        idx.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 10]);
+        assert_eq!(
+            vec![("door".to_string(), 2, 10)],
+            parse_idx(&idx, true).unwrap()
+        );
+        assert!(parse_idx(&idx[..8], false).is_err());
+
+        assert_eq!("дверь", definition_text("mдверь\0".as_bytes(), None));
+        assert_eq!("дверь", definition_text("дверь".as_bytes(), Some("m")));
+
+        let text =
+            "<k>door</k>\n[dɔː]\n<abr>n.</abr>\n1) дверь; дверца\n2) вход\n<abr>v</abr>\nстучаться";
+        assert_eq!(
+            vec![
+                sense("дверь", Some("noun")),
+                sense("дверца", Some("noun")),
+                sense("вход", Some("noun")),
+                sense("стучаться", Some("verb")),
+            ],
+            definition_senses("Door", text)
+        );
+    }
+}
@@ -0,0 +1,449 @@
+use std::error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::translate::{http, Detection, Lang, Provider, Sense, Translate, Word};
+
+use async_trait::async_trait;
+use log::{info, warn};
+
+// an open breaker waits at most this long before the next try
+const MAX_COOLDOWN: u64 = 24 * 60 * 60;
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Error {
+    description: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "All translate providers failed: {}", &self.description)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+// Circuit breaker of a provider. It opens after `failures` errors in a row
+// or at once on a quota answer. After the cooldown it is half-open: one probe is let through
+// and other requests are skipped until it ends. A successful probe closes the breaker,
+// a failed one opens it again for twice as long
+#[derive(PartialEq, Debug, Default)]
+struct Breaker {
+    failures: u32,
+    // unix time until which the provider is skipped, 0 when closed
+    open_until: u64,
+    cooldown: u64,
+    // unix time the probe was let through, 0 if there is none.
+    // A probe that never ended doesn't block the next one for longer than a cooldown
+    probe_since: u64,
+    // requests ended so far and the number at the last error. Errors of requests
+    // that were running at the same time are one error in a row
+    ended: u64,
+    last_error: u64,
+}
+
+// What the breaker knew when it let a request through
+#[derive(PartialEq, Debug, Clone, Copy)]
+struct Pass {
+    ended: u64,
+    // probe_since of the breaker if the request is the probe, 0 otherwise
+    probe: u64,
+}
+
+impl Breaker {
+    // None if the provider is skipped
+    fn pass(&mut self, now: u64) -> Option<Pass> {
+        if now < self.open_until {
+            return None;
+        }
+        let mut probe = 0;
+        if self.cooldown > 0 {
+            if self.probe_since > 0 && now < self.probe_since + self.cooldown {
+                return None;
+            }
+            self.probe_since = now;
+            probe = now;
+        }
+
+        Some(Pass {
+            ended: self.ended,
+            probe,
+        })
+    }
+
+    fn success(&mut self) {
+        *self = Breaker {
+            ended: self.ended + 1,
+            ..Breaker::default()
+        };
+    }
+
+    // returns true if the breaker has been opened
+    fn failure(&mut self, pass: Pass, quota: bool, config: &BreakerConfig, now: u64) -> bool {
+        self.ended += 1;
+        // only the latest probe, not one that was given up on
+        if pass.probe > 0 && pass.probe == self.probe_since {
+            self.probe_since = 0;
+            self.cooldown = (self.cooldown * 2).min(MAX_COOLDOWN);
+            self.open_until = now + self.cooldown;
+            return true;
+        }
+        // let through before the breaker opened or an old probe, the probe decides
+        if self.cooldown > 0 {
+            return false;
+        }
+        if self.last_error <= pass.ended {
+            self.failures += 1;
+        }
+        self.last_error = self.ended;
+        if !quota && self.failures < config.failures {
+            return false;
+        }
+        self.cooldown = config.cooldown;
+        self.open_until = now + self.cooldown;
+
+        true
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BreakerConfig {
+    // errors in a row that open the breaker
+    pub failures: u32,
+    // seconds before the first try after opening
+    pub cooldown: u64,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        BreakerConfig {
+            failures: 3,
+            cooldown: 60,
+        }
+    }
+}
+
+// what the async methods of Translate return
+type Answer<'a, T> = Pin<Box<dyn Future<Output = Result<Vec<T>, Box<dyn error::Error>>> + 'a>>;
+
+struct Link {
+    provider: Provider,
+    client: Box<dyn Translate + Send + Sync>,
+    breaker: Mutex<Breaker>,
+}
+
+// Tries providers in order and skips ones whose breaker is open.
+// An empty answer, e.g. a word missing in a dictionary, moves to the next provider too
+pub struct Chain {
+    links: Vec<Link>,
+    config: BreakerConfig,
+}
+
+impl Chain {
+    pub fn new(
+        clients: Vec<(Provider, Box<dyn Translate + Send + Sync>)>,
+        config: BreakerConfig,
+    ) -> Chain {
+        Chain {
+            links: clients
+                .into_iter()
+                .map(|(provider, client)| Link {
+                    provider,
+                    client,
+                    breaker: Mutex::new(Breaker::default()),
+                })
+                .collect(),
+            config,
+        }
+    }
+
+    // the first non-empty answer
+    async fn call<'a, T>(
+        &'a self,
+        now: u64,
+        f: impl Fn(&'a (dyn Translate + Send + Sync)) -> Answer<'a, T>,
+    ) -> Result<Vec<T>, Box<dyn error::Error>> {
+        self.ask(now, false, f).await
+    }
+
+    // answers of all providers together, e.g. languages any of them supports
+    async fn call_all<'a, T>(
+        &'a self,
+        now: u64,
+        f: impl Fn(&'a (dyn Translate + Send + Sync)) -> Answer<'a, T>,
+    ) -> Result<Vec<T>, Box<dyn error::Error>> {
+        self.ask(now, true, f).await
+    }
+
+    async fn ask<'a, T>(
+        &'a self,
+        now: u64,
+        all: bool,
+        f: impl Fn(&'a (dyn Translate + Send + Sync)) -> Answer<'a, T>,
+    ) -> Result<Vec<T>, Box<dyn error::Error>> {
+        let mut errors: Vec<String> = vec![];
+        let mut found = false;
+        let mut answers: Vec<T> = vec![];
+        for link in self.links.iter() {
+            let pass = link.breaker.lock().unwrap().pass(now);
+            let pass = match pass {
+                Some(pass) => pass,
+                None => {
+                    errors.push(format!("{:?} is unavailable", link.provider));
+                    continue;
+                }
+            };
+            match f(link.client.as_ref()).await {
+                Ok(res) => {
+                    let mut breaker = link.breaker.lock().unwrap();
+                    if breaker.cooldown > 0 {
+                        info!("{:?} translate provider is back", link.provider);
+                    }
+                    breaker.success();
+                    found = true;
+                    if !all && !res.is_empty() {
+                        return Ok(res);
+                    }
+                    answers.extend(res);
+                }
+                Err(e) => {
+                    let quota = http::is_quota(e.as_ref());
+                    let mut breaker = link.breaker.lock().unwrap();
+                    if breaker.failure(pass, quota, &self.config, now) {
+                        warn!(
+                            "{:?} translate provider is off for {}s: {}",
+                            link.provider, breaker.cooldown, e
+                        );
+                    } else {
+                        warn!("{:?} translate provider failed: {}", link.provider, e);
+                    }
+                    errors.push(format!("{:?}: {}", link.provider, e));
+                }
+            }
+        }
+        if found {
+            return Ok(answers);
+        }
+
+        Err(Box::new(Error {
+            description: errors.join("; "),
+        }))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[async_trait(?Send)]
+impl Translate for Chain {
+    async fn translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.call(unix_now(), |c| c.translate(word, to)).await
+    }
+
+    async fn senses(&self, word: &Word, to: &Lang) -> Result<Vec<Sense>, Box<dyn error::Error>> {
+        self.call(unix_now(), |c| c.senses(word, to)).await
+    }
+
+    async fn senses_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<Vec<Sense>>, Box<dyn error::Error>> {
+        self.call(unix_now(), |c| c.senses_batch(words, from, to))
+            .await
+    }
+
+    async fn detect(
+        &self,
+        text: &str,
+        langs: &[Lang],
+    ) -> Result<Vec<Detection>, Box<dyn error::Error>> {
+        self.call(unix_now(), |c| c.detect(text, langs)).await
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let mut langs = self.call_all(unix_now(), |c| c.supported_langs()).await?;
+        langs.sort();
+        langs.dedup();
+
+        Ok(langs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::translate::fallback::{Breaker, BreakerConfig, Chain, Pass};
+    use crate::translate::{http, Lang, Provider, Translate, Word};
+
+    use async_trait::async_trait;
+
+    struct Fake {
+        answer: Option<Vec<String>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait(?Send)]
+    impl Translate for Fake {
+        async fn translate(
+            &self,
+            _: &Word,
+            _: &Lang,
+        ) -> Result<Vec<String>, Box<dyn error::Error>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match &self.answer {
+                Some(a) => Ok(a.clone()),
+                None => Err("down".into()),
+            }
+        }
+
+        async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+            match &self.answer {
+                Some(a) => Ok(a.clone()),
+                None => Err("down".into()),
+            }
+        }
+    }
+
+    fn fake(answer: Option<&[&str]>) -> (Box<Fake>, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let client = Fake {
+            answer: answer.map(|a| a.iter().map(|w| w.to_string()).collect()),
+            calls: calls.clone(),
+        };
+        (Box::new(client), calls)
+    }
+
+    #[test]
+    fn breaker() {
+        let config = BreakerConfig {
+            failures: 2,
+            cooldown: 10,
+        };
+        let mut b = Breaker::default();
+        let pass = b.pass(100).unwrap();
+        assert!(!b.failure(pass, false, &config, 100));
+        let pass = b.pass(100).unwrap();
+        assert!(b.failure(pass, false, &config, 100));
+        assert_eq!(None, b.pass(109));
+        // half-open, one probe at a time
+        let probe = b.pass(110).unwrap();
+        assert_eq!(None, b.pass(111));
+        // the try after the cooldown failed
+        assert!(b.failure(probe, false, &config, 112));
+        assert_eq!(132, b.open_until);
+        let probe = b.pass(132).unwrap();
+        b.success();
+        assert_eq!(0, b.cooldown);
+        assert!(!b.failure(probe, false, &config, 133));
+        assert_eq!(1, b.failures);
+    }
+
+    #[test]
+    fn breaker_concurrent() {
+        let config = BreakerConfig {
+            failures: 2,
+            cooldown: 10,
+        };
+        let mut b = Breaker::default();
+        // errors of requests running together are one error in a row
+        let passes: Vec<Pass> = (0..3).map(|_| b.pass(100).unwrap()).collect();
+        for pass in passes {
+            assert!(!b.failure(pass, false, &config, 100));
+        }
+        assert_eq!(1, b.failures);
+        let pass = b.pass(100).unwrap();
+        assert!(b.failure(pass, false, &config, 100));
+
+        // a failed probe doubles the cooldown once, other failed requests don't change it
+        let stale = Pass { ended: 0, probe: 0 };
+        let probe = b.pass(110).unwrap();
+        assert!(b.failure(probe, false, &config, 111));
+        assert!(!b.failure(stale, true, &config, 111));
+        assert!(!b.failure(probe, false, &config, 111));
+        assert_eq!(20, b.cooldown);
+        assert_eq!(131, b.open_until);
+    }
+
+    #[test]
+    fn fallback() {
+        let (google, google_calls) = fake(None);
+        let (dict, _) = fake(Some(&[]));
+        let (libre, libre_calls) = fake(Some(&["дверь"]));
+        let chain = Chain::new(
+            vec![
+                (Provider::Google, google as Box<dyn Translate + Send + Sync>),
+                (Provider::Dictionary, dict),
+                (Provider::Libre, libre),
+            ],
+            BreakerConfig {
+                failures: 2,
+                cooldown: 60,
+            },
+        );
+        let word = Word::new("door", "en".parse().unwrap());
+        let ru: Lang = "ru".parse().unwrap();
+        for now in [0, 1, 2, 3] {
+            assert_eq!(
+                vec!["дверь"],
+                http::block_on(chain.call(now, |c| c.translate(&word, &ru))).unwrap()
+            );
+        }
+        // google is skipped after two errors
+        assert_eq!(2, google_calls.load(Ordering::SeqCst));
+        assert_eq!(4, libre_calls.load(Ordering::SeqCst));
+        http::block_on(chain.call(61, |c| c.translate(&word, &ru))).unwrap();
+        assert_eq!(3, google_calls.load(Ordering::SeqCst));
+
+        let (google, _) = fake(None);
+        let chain = Chain::new(
+            vec![(Provider::Google, google as Box<dyn Translate + Send + Sync>)],
+            BreakerConfig::default(),
+        );
+        assert_eq!(
+            "All translate providers failed: Google: down",
+            http::block_on(chain.call(0, |c| c.translate(&word, &ru)))
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn supported_langs_union() {
+        let (google, _) = fake(Some(&["en", "ru"]));
+        let (broken, _) = fake(None);
+        let (dict, _) = fake(Some(&["kk", "ru"]));
+        let chain = Chain::new(
+            vec![
+                (Provider::Google, google as Box<dyn Translate + Send + Sync>),
+                (Provider::Libre, broken),
+                (Provider::Dictionary, dict),
+            ],
+            BreakerConfig::default(),
+        );
+        // a language only the dictionary has can be translated by the chain too
+        assert_eq!(
+            vec!["en", "kk", "ru"],
+            http::block_on(chain.supported_langs()).unwrap()
+        );
+    }
+}
@@ -1,13 +1,10 @@
 use std::error;
-use std::fmt;
 
-use crate::translate::{Lang, Translate, Word};
+use crate::translate::{detect, http, langs, Detection, Lang, Sense, Translate, Word};
 
+use async_trait::async_trait;
 use hyper;
-use hyper::body::HttpBody;
-use hyper_tls::HttpsConnector;
 use serde::{Deserialize, Serialize};
-use tokio::runtime::Builder;
 
 const API_HOST: &str = "translation.googleapis.com";
 
@@ -27,9 +24,12 @@ struct SupportedLangsList {
     pub languages: Vec<SupportedLang>,
 }
 
+// the API takes at most 128 texts in a request
+const MAX_BATCH: usize = 128;
+
 #[derive(Serialize)]
 struct Query {
-    q: String,
+    q: Vec<String>,
     target: String,
     //format: String,
     source: String,
@@ -55,30 +55,28 @@ struct TranslateTextResponseTranslation {
     pub translated_text: String,
 }
 
-#[derive(PartialEq, Debug, Clone)]
-pub struct Error {
-    description: String,
+#[derive(Serialize)]
+struct DetectQuery {
+    q: String,
+    key: String,
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Response: {}", &self.description)
-    }
+#[derive(Deserialize)]
+struct DetectResponse {
+    pub data: DetectionsList,
 }
 
-impl error::Error for Error {
-    /*fn description(&self) -> &str {
-        let m = format!("{}", &self);
-        m.as_str()
-    }*/
-
-    /*fn cause(&self) -> Option<&(dyn error::Error)> {
-        None
-    }*/
+#[derive(Deserialize)]
+struct DetectionsList {
+    // one list of guesses for every text of the query
+    pub detections: Vec<Vec<DetectResponseDetection>>,
+}
 
-    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        None
-    }
+#[derive(Deserialize)]
+struct DetectResponseDetection {
+    pub language: String,
+    #[serde(default)]
+    pub confidence: f64,
 }
 
 pub struct Client {
@@ -99,89 +97,88 @@ impl Client {
         word: &Word,
         to: &Lang,
     ) -> Result<Vec<String>, Box<dyn error::Error>> {
-        let q = Query {
-            q: word.word.to_string(),
-            target: to.lang.to_string(),
-            source: word.lang.lang.to_string(),
+        self.async_translate_batch(&[word.word.to_string()], &word.lang, to)
+            .await
+    }
+
+    // One translation for every word, in the same order
+    pub async fn async_translate_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let mut trs: Vec<String> = vec![];
+        for chunk in words.chunks(MAX_BATCH) {
+            let q = Query {
+                q: chunk.to_vec(),
+                target: to.lang.to_string(),
+                source: from.lang.to_string(),
+                key: self.token.to_string(),
+            };
+            let url = format!(
+                "https://{}/language/translate/v2?key={}",
+                API_HOST, self.token
+            );
+            let req = hyper::Request::builder()
+                .method(hyper::Method::POST)
+                .uri(url)
+                .body(hyper::Body::from(serde_json::to_string(&q)?))?;
+            let res: TranslatesResponse = http::send(req).await?;
+            if res.data.translations.len() != chunk.len() {
+                return Err(Box::new(http::Error::new(format!(
+                    "{} translations for {} words",
+                    res.data.translations.len(),
+                    chunk.len()
+                ))));
+            }
+            for t in res.data.translations {
+                trs.push(t.translated_text.to_string())
+            }
+        }
+
+        Ok(trs)
+    }
+
+    pub async fn async_detect(&self, text: &str) -> Result<Vec<Detection>, Box<dyn error::Error>> {
+        let q = DetectQuery {
+            q: text.to_string(),
             key: self.token.to_string(),
         };
-        let https = HttpsConnector::new();
-        let client = hyper::Client::builder().build::<_, hyper::Body>(https);
         let url = format!(
-            "https://{}/language/translate/v2?key={}",
+            "https://{}/language/translate/v2/detect?key={}",
             API_HOST, self.token
         );
         let req = hyper::Request::builder()
             .method(hyper::Method::POST)
             .uri(url)
             .body(hyper::Body::from(serde_json::to_string(&q)?))?;
-        let mut resp = client.request(req).await?;
-        let mut body: Vec<u8> = vec![];
-        while let Some(chunk) = resp.body_mut().data().await {
-            let bt = chunk?;
-            for b in bt.iter() {
-                body.push(*b)
-            }
-        }
-        /*println!(
-            "{:?} \n {}",
-            String::from_utf8(body.to_vec()),
-            serde_json::to_string(&q)?
-        );*/
-        let serde_r = serde_json::from_slice(body.as_slice());
-        let res: TranslatesResponse = match serde_r {
-            Ok(res) => res,
-            Err(e) => {
-                let msg = format!(
-                    "Can't unmarshal: {}. {}",
-                    e,
-                    String::from_utf8(body.to_vec())?
-                );
-                return Err(Box::new(Error { description: msg }));
-            }
-        };
+        let res: DetectResponse = http::send(req).await?;
 
-        let mut trs: Vec<String> = vec![];
-        for t in res.data.translations {
-            trs.push(t.translated_text.to_string())
-        }
-
-        Ok(trs)
+        Ok(res
+            .data
+            .detections
+            .into_iter()
+            .flatten()
+            .map(|d| Detection {
+                lang: Lang {
+                    lang: langs::normalize(&d.language),
+                },
+                confidence: d.confidence,
+            })
+            .collect())
     }
 
     pub async fn async_supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
-        let https = HttpsConnector::new();
-        let client = hyper::Client::builder().build::<_, hyper::Body>(https);
-        let url_s = format!(
+        let url = format!(
             "https://{}/language/translate/v2/languages?key={}",
             API_HOST, self.token
         );
-        let url: hyper::Uri = url_s.parse()?;
-        let mut resp = client.get(url).await?;
-        let mut body: Vec<u8> = vec![];
-        while let Some(chunk) = resp.body_mut().data().await {
-            let bt = chunk?;
-            for b in bt.iter() {
-                body.push(*b)
-            }
-        }
-        /*println!(
-            "{:?} \n {}",
-            String::from_utf8(body.to_vec()),
-            serde_json::to_string(&q)?
-        );*/
-        let serde_r = serde_json::from_slice(body.as_slice());
-        let res: SupportedLangsResponse = match serde_r {
-            Ok(res) => res,
-            Err(e) => {
-                let msg = format!(
-                    "Can't unmarshal: {}. {}",
-                    e,
-                    String::from_utf8(body.to_vec())?
-                );
-                return Err(Box::new(Error { description: msg }));
-            }
-        };
+        let req = hyper::Request::builder()
+            .method(hyper::Method::GET)
+            .uri(url)
+            .body(hyper::Body::empty())?;
+        let res: SupportedLangsResponse = http::send(req).await?;
 
         let mut langs: Vec<String> = vec![];
         for l in res.data.languages {
@@ -192,39 +189,60 @@ impl Client {
     }
 }
 
+#[async_trait(?Send)]
 impl Translate for Client {
-    fn translate(&self, word: &Word, to: &Lang) -> Result<Vec<String>, Box<dyn error::Error>> {
-        let rt = Builder::new_current_thread().enable_all().build().unwrap();
-        rt.block_on(self.async_translate(word, to))
-    }
-    fn translate_to_langs(
+    async fn translate(
         &self,
         word: &Word,
-        langs: Vec<Lang>,
-    ) -> Result<Vec<Word>, Box<dyn error::Error>> {
-        let mut res = vec![];
-        for lang in langs {
-            let trs = self.translate(word, &lang)?;
-            for w in trs {
-                res.push(Word {
-                    word: w,
-                    lang: lang.clone(),
-                })
-            }
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_translate(word, to).await
+    }
+
+    async fn senses_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<Vec<Sense>>, Box<dyn error::Error>> {
+        let trs = self.async_translate_batch(words, from, to).await?;
+
+        Ok(trs
+            .into_iter()
+            .map(|t| vec![Sense { word: t, pos: None }])
+            .collect())
+    }
+
+    // Google guesses among all languages, the local detector is left
+    // when the guess isn't one of the user's languages
+    async fn detect(
+        &self,
+        text: &str,
+        langs: &[Lang],
+    ) -> Result<Vec<Detection>, Box<dyn error::Error>> {
+        let mut res: Vec<Detection> = self
+            .async_detect(text)
+            .await?
+            .into_iter()
+            .filter(|d| langs.contains(&d.lang))
+            .collect();
+        if res.is_empty() {
+            return Ok(detect::local(text, langs));
         }
+        res.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
 
         Ok(res)
     }
-    fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
-        let rt = Builder::new_current_thread().enable_all().build().unwrap();
-        rt.block_on(self.async_supported_langs())
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_supported_langs().await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::translate::google::Client;
-    use crate::translate::{Lang, Translate, Word};
+    use crate::translate::{http, Lang, Translate, Word};
     use std::env;
 
     #[test]
@@ -237,26 +255,27 @@ mod tests {
                 "".to_string()
             }
         };
-        if translate_token == "" {
+        if translate_token.is_empty() {
             return;
         }
         let g = Client::new(&translate_token);
-        match g.translate(
+        match http::block_on(g.translate(
             &Word {
                 word: "word".to_string(),
                 lang: Lang {
                     lang: "en".to_string(),
                 },
+                pos: None,
+                manual: false,
             },
             &Lang {
                 lang: "ru".to_string(),
             },
-        ) {
+        )) {
             Ok(trs) => {
                 trs.iter().for_each(|s| println!("Translate: {}\n", s));
-                return;
             }
-            Err(e) => assert!(false, "{}", e),
+            Err(e) => panic!("{}", e),
         }
     }
 
@@ -270,16 +289,15 @@ mod tests {
                 "".to_string()
             }
         };
-        if translate_token == "" {
+        if translate_token.is_empty() {
             return;
         }
         let g = Client::new(&translate_token);
-        match g.supported_langs() {
+        match http::block_on(g.supported_langs()) {
             Ok(trs) => {
                 trs.iter().for_each(|s| println!("Lang: {}\n", s));
-                return;
             }
-            Err(e) => assert!(false, "{}", e),
+            Err(e) => panic!("{}", e),
         }
     }
 }
@@ -0,0 +1,118 @@
+use std::error;
+use std::fmt;
+use std::future::Future;
+use std::sync::OnceLock;
+
+use hyper::client::HttpConnector;
+use hyper::{Body, Request};
+use hyper_tls::HttpsConnector;
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+
+// Pooled connections belong to the runtime that opened them,
+// so all provider requests run on the one runtime and share one client
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+static CLIENT: OnceLock<hyper::Client<HttpsConnector<HttpConnector>>> = OnceLock::new();
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct Error {
+    description: String,
+    // HTTP status of a provider answer, None if the answer isn't readable
+    status: Option<u16>,
+}
+
+impl Error {
+    pub fn new(description: String) -> Error {
+        Error {
+            description,
+            status: None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Response: {}", &self.description)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+// Sends the request to a provider API and decodes the JSON answer
+pub async fn send<T: DeserializeOwned>(req: Request<Body>) -> Result<T, Box<dyn error::Error>> {
+    let client = CLIENT.get_or_init(|| hyper::Client::builder().build(HttpsConnector::new()));
+    let resp = client.request(req).await?;
+    let status = resp.status();
+    let body = hyper::body::to_bytes(resp.into_body()).await?;
+    if !status.is_success() {
+        return Err(Box::new(Error {
+            description: format!("Status {}. {}", status, String::from_utf8_lossy(&body)),
+            status: Some(status.as_u16()),
+        }));
+    }
+    match serde_json::from_slice(&body) {
+        Ok(res) => Ok(res),
+        Err(e) => Err(Box::new(Error {
+            description: format!("Can't unmarshal: {}. {}", e, String::from_utf8_lossy(&body)),
+            status: None,
+        })),
+    }
+}
+
+// Provider answers for exhausted limits: 429 everywhere, 456 by DeepL,
+// 403 with a quota reason by Google
+pub fn is_quota(e: &(dyn error::Error + 'static)) -> bool {
+    match e.downcast_ref::<Error>() {
+        Some(Error {
+            status: Some(429) | Some(456),
+            ..
+        }) => true,
+        Some(Error {
+            status: Some(403),
+            description,
+        }) => {
+            let d = description.to_lowercase();
+            d.contains("quota") || d.contains("ratelimit")
+        }
+        _ => false,
+    }
+}
+
+// Runs translation from blocking code like the telegram loop.
+// Must not be called inside another runtime
+pub fn block_on<F: Future>(f: F) -> F::Output {
+    let rt = RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("translate")
+            .enable_all()
+            .build()
+            .unwrap()
+    });
+    rt.block_on(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::http::{is_quota, Error};
+
+    #[test]
+    fn quota() {
+        let err = |status: Option<u16>, description: &str| Error {
+            description: description.to_string(),
+            status,
+        };
+        assert!(is_quota(&err(Some(429), "")));
+        assert!(is_quota(&err(
+            Some(403),
+            "Status 403. dailyLimitExceeded quotaExceeded"
+        )));
+        assert!(!is_quota(&err(Some(403), "Status 403. Invalid key")));
+        assert!(!is_quota(&err(Some(500), "")));
+        assert!(!is_quota(&err(None, "Can't unmarshal")));
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::RwLock;
+
+use log::info;
+
+// Code, English name and other names users type: native and Russian
+const NAMES: [(&str, &str, &[&str]); 36] = [
+    ("ar", "Arabic", &["العربية", "арабский"]),
+    ("az", "Azerbaijani", &["azərbaycan", "азербайджанский"]),
+    ("be", "Belarusian", &["беларуская", "белорусский"]),
+    ("bg", "Bulgarian", &["български", "болгарский"]),
+    ("cs", "Czech", &["čeština", "чешский"]),
+    ("da", "Danish", &["dansk", "датский"]),
+    ("de", "German", &["deutsch", "немецкий"]),
+    ("el", "Greek", &["ελληνικά", "греческий"]),
+    ("en", "English", &["английский"]),
+    ("es", "Spanish", &["español", "испанский"]),
+    ("et", "Estonian", &["eesti", "эстонский"]),
+    ("fa", "Persian", &["فارسی", "персидский"]),
+    ("fi", "Finnish", &["suomi", "финский"]),
+    ("fr", "French", &["français", "французский"]),
+    ("he", "Hebrew", &["עברית", "иврит"]),
+    ("hi", "Hindi", &["हिन्दी", "хинди"]),
+    ("hu", "Hungarian", &["magyar", "венгерский"]),
+    ("hy", "Armenian", &["հայերեն", "армянский"]),
+    ("it", "Italian", &["italiano", "итальянский"]),
+    ("ja", "Japanese", &["日本語", "японский"]),
+    ("ka", "Georgian", &["ქართული", "грузинский"]),
+    ("kk", "Kazakh", &["қазақша", "қазақ", "казахский"]),
+    ("ko", "Korean", &["한국어", "корейский"]),
+    ("ky", "Kyrgyz", &["кыргызча", "киргизский"]),
+    ("lt", "Lithuanian", &["lietuvių", "литовский"]),
+    ("lv", "Latvian", &["latviešu", "латышский"]),
+    ("nl", "Dutch", &["nederlands", "голландский"]),
+    ("no", "Norwegian", &["norsk", "норвежский"]),
+    ("pl", "Polish", &["polski", "польский"]),
+    ("pt", "Portuguese", &["português", "португальский"]),
+    ("ro", "Romanian", &["română", "румынский"]),
+    ("ru", "Russian", &["русский"]),
+    ("sv", "Swedish", &["svenska", "шведский"]),
+    ("tr", "Turkish", &["türkçe", "турецкий"]),
+    ("uk", "Ukrainian", &["українська", "украинский"]),
+    ("zh", "Chinese", &["中文", "китайский"]),
+];
+
+// Languages of the active provider. Until the provider answers every known name is accepted
+#[derive(Default)]
+pub struct Registry {
+    available: Option<Vec<String>>,
+}
+
+impl Registry {
+    // Code of the language by its code, name or alias if the provider supports it
+    pub fn resolve(&self, s: &str) -> Option<String> {
+        let s = s.trim().to_lowercase();
+        let code = NAMES
+            .iter()
+            .find(|(code, name, aliases)| {
+                *code == s || name.to_lowercase() == s || aliases.contains(&s.as_str())
+            })
+            .map(|(code, _, _)| code.to_string());
+        match &self.available {
+            Some(available) => {
+                let code = code.unwrap_or(s);
+                if available.contains(&code) {
+                    Some(code)
+                } else {
+                    None
+                }
+            }
+            None => code,
+        }
+    }
+
+    pub fn set_available(&mut self, codes: &[String]) {
+        let mut available: Vec<String> = vec![];
+        for c in codes {
+            let code = normalize(c);
+            if !code.is_empty() && !available.contains(&code) {
+                available.push(code)
+            }
+        }
+        available.sort();
+        self.available = Some(available);
+    }
+
+    pub fn available(&self) -> Vec<String> {
+        match &self.available {
+            Some(available) => available.clone(),
+            None => NAMES.iter().map(|(code, _, _)| code.to_string()).collect(),
+        }
+    }
+}
+
+// "zh-CN" and "zh-TW" are "zh" for us, Google still calls Hebrew "iw"
+pub fn normalize(code: &str) -> String {
+    let code = code.trim().to_lowercase();
+    let code = code.split(['-', '_']).next().unwrap_or_default();
+    match code {
+        "iw" => "he".to_string(),
+        c => c.to_string(),
+    }
+}
+
+static REGISTRY: RwLock<Registry> = RwLock::new(Registry { available: None });
+
+pub fn resolve(s: &str) -> Option<String> {
+    REGISTRY.read().unwrap().resolve(s)
+}
+
+pub fn set_available(codes: &[String]) {
+    let mut registry = REGISTRY.write().unwrap();
+    registry.set_available(codes);
+    info!("{} languages available", registry.available().len());
+}
+
+pub fn available() -> Vec<String> {
+    REGISTRY.read().unwrap().available()
+}
+
+// Whether the code is in the table, unlike resolve it doesn't depend on the provider
+pub fn known(code: &str) -> bool {
+    NAMES.iter().any(|(c, _, _)| *c == code)
+}
+
+// English name, the code itself for languages missing in the table
+pub fn name(code: &str) -> String {
+    NAMES
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map_or(code.to_string(), |(_, name, _)| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::langs::{known, name, normalize, Registry};
+
+    #[test]
+    fn resolve() {
+        let mut registry = Registry::default();
+        assert_eq!(Some("en".to_string()), registry.resolve(" English"));
+        assert_eq!(Some("de".to_string()), registry.resolve("Deutsch"));
+        assert_eq!(Some("ja".to_string()), registry.resolve("японский"));
+        assert_eq!(None, registry.resolve("sw"));
+
+        let codes: Vec<String> = ["EN", "de", "zh-CN", "zh-TW", "iw", "sw"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        registry.set_available(&codes);
+        assert_eq!(vec!["de", "en", "he", "sw", "zh"], registry.available());
+        assert_eq!(Some("sw".to_string()), registry.resolve("sw"));
+        assert_eq!(Some("he".to_string()), registry.resolve("hebrew"));
+        assert_eq!(None, registry.resolve("ru"));
+
+        assert_eq!("Kazakh", name("kk"));
+        assert_eq!("sw", name("sw"));
+        assert!(known(&normalize("zh-CN")));
+        assert!(!known("sw"));
+    }
+}
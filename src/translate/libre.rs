@@ -0,0 +1,131 @@
+use std::error;
+
+use crate::translate::{http, Lang, Translate, Word};
+
+use async_trait::async_trait;
+use hyper;
+use serde::{Deserialize, Serialize};
+
+const API_URL: &str = "https://libretranslate.com";
+// variants asked in addition to the main translation
+const ALTERNATIVES: u8 = 2;
+
+#[derive(Serialize)]
+struct Query {
+    q: String,
+    source: String,
+    target: String,
+    format: String,
+    alternatives: u8,
+    // self-hosted servers may work without a key
+    #[serde(skip_serializing_if = "String::is_empty")]
+    api_key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslateResponse {
+    pub translated_text: String,
+    // only newer servers return alternatives
+    #[serde(default)]
+    pub alternatives: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SupportedLang {
+    pub code: String,
+}
+
+pub struct Client {
+    token: String,
+    url: String,
+}
+
+impl Client {
+    // url of a self-hosted server, the public one by default
+    pub fn new(token: &str, url: Option<&str>) -> Client {
+        Client {
+            token: token.to_string(),
+            url: url.unwrap_or(API_URL).trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+impl Client {
+    pub async fn async_translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let q = Query {
+            q: word.word.to_string(),
+            source: word.lang.lang.to_string(),
+            target: to.lang.to_string(),
+            format: "text".to_string(),
+            alternatives: ALTERNATIVES,
+            api_key: self.token.to_string(),
+        };
+        let req = hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .uri(format!("{}/translate", self.url))
+            .header("Content-Type", "application/json")
+            .body(hyper::Body::from(serde_json::to_string(&q)?))?;
+        let res: TranslateResponse = http::send(req).await?;
+
+        Ok(translates(res))
+    }
+
+    pub async fn async_supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let req = hyper::Request::builder()
+            .method(hyper::Method::GET)
+            .uri(format!("{}/languages", self.url))
+            .body(hyper::Body::empty())?;
+        let res: Vec<SupportedLang> = http::send(req).await?;
+
+        Ok(res.into_iter().map(|l| l.code).collect())
+    }
+}
+
+fn translates(res: TranslateResponse) -> Vec<String> {
+    let mut trs = vec![res.translated_text];
+    for alt in res.alternatives {
+        if !trs.contains(&alt) {
+            trs.push(alt)
+        }
+    }
+
+    trs
+}
+
+#[async_trait(?Send)]
+impl Translate for Client {
+    async fn translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_translate(word, to).await
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_supported_langs().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::libre::{translates, TranslateResponse};
+
+    #[test]
+    fn response() {
+        let res: TranslateResponse = serde_json::from_str(
+            r#"{"translatedText": "дверь", "alternatives": ["дверца", "дверь"]}"#,
+        )
+        .unwrap();
+        assert_eq!(vec!["дверь", "дверца"], translates(res));
+
+        let res: TranslateResponse =
+            serde_json::from_str(r#"{"translatedText": "дверь"}"#).unwrap();
+        assert_eq!(vec!["дверь"], translates(res));
+    }
+}
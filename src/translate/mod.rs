@@ -1,10 +1,22 @@
+pub mod cache;
+pub mod deepl;
+pub mod detect;
+pub mod dictionary;
+pub mod fallback;
 pub mod google;
+pub mod http;
+pub mod langs;
+pub mod libre;
+pub mod yandex;
 
 use std::error::Error;
 use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use crate::storage::Word;
+use async_trait::async_trait;
+use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Clone, PartialEq, Debug, Eq, Hash)]
@@ -23,23 +35,23 @@ impl fmt::Display for LangParseError {
     }
 }
 
-const SUPPORTED_LANGS: [&str; 4] = ["ru", "en", "kk", "de"];
-
+// Accepts codes and names of languages the provider supports, "english" is "en"
 impl FromStr for Lang {
     type Err = LangParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut st = s.to_lowercase();
-        st = st.trim().to_string();
-        for l in SUPPORTED_LANGS {
-            if st == l {
-                return Ok(Lang { lang: st });
-            }
+        match langs::resolve(s) {
+            Some(lang) => Ok(Lang { lang }),
+            None => Err(LangParseError {
+                description: "Unsupported language".to_string(),
+            }),
         }
+    }
+}
 
-        Err(LangParseError {
-            description: "Unsupported language".to_string(),
-        })
+impl Lang {
+    pub fn name(&self) -> String {
+        langs::name(&self.lang)
     }
 }
 
@@ -49,14 +61,247 @@ impl fmt::Display for Lang {
     }
 }
 
+// One meaning of a translated word
+#[derive(Deserialize, Serialize, Clone, PartialEq, Debug)]
+pub struct Sense {
+    pub word: String,
+    // part of speech, machine translation doesn't know it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pos: Option<String>,
+}
+
+// Guess of the language of a text, the confidence is from 0 to 1
+#[derive(Clone, PartialEq, Debug)]
+pub struct Detection {
+    pub lang: Lang,
+    pub confidence: f64,
+}
+
+// Providers are async so that translations to several languages run concurrently,
+// blocking code calls them through http::block_on
+#[async_trait(?Send)]
 pub trait Translate {
-    fn translate(&self, word: &Word, to: &Lang) -> Result<Vec<String>, Box<dyn Error>>;
+    async fn translate(&self, word: &Word, to: &Lang) -> Result<Vec<String>, Box<dyn Error>>;
 
-    fn translate_to_langs(
+    // Providers with richer data than a plain translation override it
+    async fn senses(&self, word: &Word, to: &Lang) -> Result<Vec<Sense>, Box<dyn Error>> {
+        Ok(self
+            .translate(word, to)
+            .await?
+            .into_iter()
+            .map(|w| Sense { word: w, pos: None })
+            .collect())
+    }
+
+    // Senses of several words in one language, one list per word in the same order.
+    // Providers that take many texts in a request override it
+    async fn senses_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<Vec<Sense>>, Box<dyn Error>> {
+        let mut res = vec![];
+        for w in words {
+            let word = Word::new(w, from.clone());
+            res.push(self.senses(&word, to).await?)
+        }
+
+        Ok(res)
+    }
+
+    async fn translate_to_langs(
         &self,
         word: &Word,
         langs: Vec<Lang>,
-    ) -> Result<Vec<Word>, Box<dyn Error>>;
+    ) -> Result<Vec<Word>, Box<dyn Error>> {
+        let senses = join_all(langs.iter().map(|lang| self.senses(word, lang))).await;
+        let mut res = vec![];
+        for (lang, senses) in langs.iter().zip(senses) {
+            for s in senses? {
+                res.push(Word {
+                    word: s.word,
+                    lang: lang.clone(),
+                    pos: s.pos,
+                    manual: false,
+                })
+            }
+        }
+
+        Ok(res)
+    }
+
+    // Likely languages of the text among the given ones, the most likely first.
+    // Providers with a detection API override it
+    async fn detect(&self, text: &str, langs: &[Lang]) -> Result<Vec<Detection>, Box<dyn Error>> {
+        Ok(detect::local(text, langs))
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn Error>>;
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Provider {
+    Google,
+    DeepL,
+    Libre,
+    Yandex,
+    // local dictionary files, no key and no network
+    Dictionary,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ProviderParseError {
+    pub description: String,
+}
+
+impl fmt::Display for ProviderParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
 
-    fn supported_langs(&self) -> Result<Vec<String>, Box<dyn Error>>;
+impl FromStr for Provider {
+    type Err = ProviderParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "google" => Ok(Provider::Google),
+            "deepl" => Ok(Provider::DeepL),
+            "libre" | "libretranslate" => Ok(Provider::Libre),
+            "yandex" => Ok(Provider::Yandex),
+            "dictionary" | "offline" => Ok(Provider::Dictionary),
+            _ => Err(ProviderParseError {
+                description: format!("Unknown translate provider: {}", s),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Provider::Google => "google",
+            Provider::DeepL => "deepl",
+            Provider::Libre => "libre",
+            Provider::Yandex => "yandex",
+            Provider::Dictionary => "dictionary",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+pub struct Config {
+    // tried in order, more than one makes a fallback chain
+    pub providers: Vec<Provider>,
+    pub breaker: fallback::BreakerConfig,
+    // API key, not needed for dictionaries and self-hosted LibreTranslate
+    pub token: String,
+    // API address for DeepL and LibreTranslate
+    pub url: Option<String>,
+    // Yandex Cloud folder
+    pub folder: Option<String>,
+    // dictionary files, see dictionary::Dictionary::load
+    pub dictionaries: Vec<String>,
+    // translations shared by all providers, not used for dictionaries
+    pub cache: Option<Arc<cache::Store>>,
+}
+
+pub fn client(config: &Config) -> Result<Box<dyn Translate + Send + Sync>, Box<dyn Error>> {
+    let mut clients = vec![];
+    for provider in config.providers.iter() {
+        clients.push((*provider, provider_client(*provider, config)?));
+    }
+    if clients.len() == 1 {
+        return Ok(clients.remove(0).1);
+    }
+
+    Ok(Box::new(fallback::Chain::new(clients, config.breaker)))
+}
+
+fn provider_client(
+    provider: Provider,
+    config: &Config,
+) -> Result<Box<dyn Translate + Send + Sync>, Box<dyn Error>> {
+    let token = config.token.as_str();
+    let url = config.url.as_deref();
+    let client: Box<dyn Translate + Send + Sync> = match provider {
+        Provider::Google => Box::new(google::Client::new(token)),
+        Provider::DeepL => Box::new(deepl::Client::new(token, url)),
+        Provider::Libre => Box::new(libre::Client::new(token, url)),
+        Provider::Yandex => Box::new(yandex::Client::new(token, config.folder.as_deref())),
+        Provider::Dictionary => {
+            return Ok(Box::new(dictionary::Dictionary::load(
+                &config.dictionaries,
+            )?));
+        }
+    };
+
+    Ok(match &config.cache {
+        Some(store) => Box::new(cache::Cache::new(store.clone(), provider, client)),
+        None => client,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::storage::Word;
+    use crate::translate::{http, Lang, Provider, Translate};
+
+    use async_trait::async_trait;
+
+    // remembers how many requests were in flight at once
+    #[derive(Default)]
+    struct Slow {
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+    }
+
+    #[async_trait(?Send)]
+    impl Translate for Slow {
+        async fn translate(
+            &self,
+            word: &Word,
+            to: &Lang,
+        ) -> Result<Vec<String>, Box<dyn error::Error>> {
+            let n = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(n, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(vec![format!("{} {}", word.word, to)])
+        }
+
+        async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn translate_to_langs_concurrently() {
+        let slow = Slow::default();
+        let word = Word::new("door", "en".parse().unwrap());
+        let langs: Vec<Lang> = ["ru", "kk", "de"]
+            .iter()
+            .map(|l| l.parse().unwrap())
+            .collect();
+        let res = http::block_on(slow.translate_to_langs(&word, langs)).unwrap();
+        assert_eq!(
+            vec!["door ru", "door kk", "door de"],
+            res.iter().map(|w| w.word.as_str()).collect::<Vec<&str>>()
+        );
+        assert_eq!("kk", res[1].lang.lang);
+        assert_eq!(3, slow.max_running.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn parse_provider() {
+        assert_eq!(Ok(Provider::DeepL), "DeepL".parse());
+        assert_eq!(Ok(Provider::Libre), "libretranslate".parse());
+        assert_eq!(Ok(Provider::Yandex), " yandex".parse());
+        assert!("bing".parse::<Provider>().is_err());
+    }
 }
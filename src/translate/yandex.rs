@@ -0,0 +1,180 @@
+use std::error;
+
+use crate::translate::{http, Lang, Sense, Translate, Word};
+
+use async_trait::async_trait;
+use hyper;
+use serde::{Deserialize, Serialize};
+
+const API_URL: &str = "https://translate.api.cloud.yandex.net/translate/v2";
+// a request takes at most 10000 characters, words are short
+const MAX_BATCH: usize = 100;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Query {
+    source_language_code: String,
+    target_language_code: String,
+    texts: Vec<String>,
+    // needed only for user accounts, API keys of service accounts know their folder
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LangsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    folder_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TranslatesResponse {
+    pub translations: Vec<TranslationResponse>,
+}
+
+#[derive(Deserialize)]
+struct TranslationResponse {
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct SupportedLangsResponse {
+    pub languages: Vec<SupportedLang>,
+}
+
+#[derive(Deserialize)]
+struct SupportedLang {
+    pub code: String,
+}
+
+pub struct Client {
+    token: String,
+    folder: Option<String>,
+}
+
+impl Client {
+    pub fn new(token: &str, folder: Option<&str>) -> Client {
+        Client {
+            token: token.to_string(),
+            folder: folder.map(|f| f.to_string()),
+        }
+    }
+
+    fn request(
+        &self,
+        path: &str,
+        body: String,
+    ) -> Result<hyper::Request<hyper::Body>, Box<dyn error::Error>> {
+        Ok(hyper::Request::builder()
+            .method(hyper::Method::POST)
+            .uri(format!("{}{}", API_URL, path))
+            .header("Authorization", format!("Api-Key {}", self.token))
+            .header("Content-Type", "application/json")
+            .body(hyper::Body::from(body))?)
+    }
+}
+
+impl Client {
+    pub async fn async_translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_translate_batch(&[word.word.to_string()], &word.lang, to)
+            .await
+    }
+
+    // One translation for every word, in the same order
+    pub async fn async_translate_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let mut trs: Vec<String> = vec![];
+        for chunk in words.chunks(MAX_BATCH) {
+            let q = Query {
+                source_language_code: from.lang.to_string(),
+                target_language_code: to.lang.to_string(),
+                texts: chunk.to_vec(),
+                folder_id: self.folder.clone(),
+            };
+            let req = self.request("/translate", serde_json::to_string(&q)?)?;
+            let res: TranslatesResponse = http::send(req).await?;
+            if res.translations.len() != chunk.len() {
+                return Err(Box::new(http::Error::new(format!(
+                    "{} translations for {} words",
+                    res.translations.len(),
+                    chunk.len()
+                ))));
+            }
+            trs.extend(res.translations.into_iter().map(|t| t.text));
+        }
+
+        Ok(trs)
+    }
+
+    pub async fn async_supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        let q = LangsQuery {
+            folder_id: self.folder.clone(),
+        };
+        let req = self.request("/languages", serde_json::to_string(&q)?)?;
+        let res: SupportedLangsResponse = http::send(req).await?;
+
+        Ok(res.languages.into_iter().map(|l| l.code).collect())
+    }
+}
+
+#[async_trait(?Send)]
+impl Translate for Client {
+    async fn translate(
+        &self,
+        word: &Word,
+        to: &Lang,
+    ) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_translate(word, to).await
+    }
+
+    async fn senses_batch(
+        &self,
+        words: &[String],
+        from: &Lang,
+        to: &Lang,
+    ) -> Result<Vec<Vec<Sense>>, Box<dyn error::Error>> {
+        let trs = self.async_translate_batch(words, from, to).await?;
+
+        Ok(trs
+            .into_iter()
+            .map(|t| vec![Sense { word: t, pos: None }])
+            .collect())
+    }
+
+    async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+        self.async_supported_langs().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::translate::yandex::{Query, TranslatesResponse};
+
+    #[test]
+    fn query_and_response() {
+        let q = Query {
+            source_language_code: "en".to_string(),
+            target_language_code: "ru".to_string(),
+            texts: vec!["door".to_string()],
+            folder_id: None,
+        };
+        assert_eq!(
+            r#"{"sourceLanguageCode":"en","targetLanguageCode":"ru","texts":["door"]}"#,
+            serde_json::to_string(&q).unwrap()
+        );
+        let res: TranslatesResponse = serde_json::from_str(
+            r#"{"translations": [{"text": "дверь", "detectedLanguageCode": "en"}]}"#,
+        )
+        .unwrap();
+        assert_eq!("дверь", res.translations[0].text);
+    }
+}
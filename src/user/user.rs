@@ -1,16 +1,41 @@
 use std::error;
 use std::fmt;
-use std::sync::{Arc, RwLock};
-use std::time::SystemTime;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
 
 use crate::storage;
-use crate::storage::{strategy, Storage, Word};
-use crate::translate::Lang;
-use crate::translate::{google, Translate};
+use crate::storage::schedule::{Grade, DAY};
+use crate::storage::{same_word, strategy, Backend, Word};
+use crate::translate::Translate;
+use crate::translate::{detect, http, langs, Lang};
+
+use futures::future::join_all;
+use log::{error, info, warn};
+use serde::Serialize;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// a less sure guess of the language is confirmed by the user
+const MIN_CONFIDENCE: f64 = 0.5;
+
+// Result of a word in a batch import
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Added {
+    pub word: String,
+    pub error: Option<String>,
+}
+
+// Language of a word typed without one
+#[derive(Debug, PartialEq)]
+pub enum Detected {
+    Sure(Lang),
+    // the user's languages to choose from, likely ones first
+    Unsure(Vec<Lang>),
+}
 
 pub struct UserWords {
-    storage: Arc<RwLock<Storage>>,
-    translator: google::Client,
+    storage: Arc<dyn Backend + Send + Sync>,
+    translator: Box<dyn Translate + Send + Sync>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -49,21 +74,23 @@ impl error::Error for UserError {
 }
 
 impl UserWords {
-    pub fn new(stor: Arc<RwLock<Storage>>, tran: google::Client) -> UserWords {
+    pub fn new(
+        stor: Arc<dyn Backend + Send + Sync>,
+        tran: Box<dyn Translate + Send + Sync>,
+    ) -> UserWords {
         UserWords {
             storage: stor,
             translator: tran,
         }
     }
 
-    pub fn add_word(&mut self, user_id: i64, word: &Word) -> Result<(), Box<dyn error::Error>> {
-        let mut stor = self.storage.write().unwrap();
-        let langs: Vec<Lang> = match stor.get(user_id) {
+    pub fn add_word(&self, user_id: i64, word: &Word) -> Result<(), Box<dyn error::Error>> {
+        let langs: Vec<Lang> = match self.storage.get(user_id)? {
             Some(user) => user
                 .langs
                 .iter()
                 .filter(|l| l.lang != word.lang.lang)
-                .map(|l| l.clone())
+                .cloned()
                 .collect(),
             None => vec![],
         };
@@ -72,23 +99,258 @@ impl UserWords {
                 kind: UserErrorKind::NoLang,
             }));
         }
-        let tran = storage::Translate {
-            word: word.clone(),
-            translates: self.translator.translate_to_langs(&word, langs)?,
-            last_seen: 0,
+        let tran = storage::Translate::new(
+            word.clone(),
+            http::block_on(self.translator.translate_to_langs(word, langs))?,
+        );
+
+        self.storage
+            .upsert(user_id, &strategy::AddTranslate { tran })
+    }
+
+    // Guesses the language of the word among the user's languages.
+    // A failed provider leaves the guess to the local detector
+    pub fn detect_lang(&self, user_id: i64, word: &str) -> Result<Detected, Box<dyn error::Error>> {
+        let langs = self.list_langs(user_id)?;
+        if langs.is_empty() {
+            return Err(Box::new(UserError {
+                kind: UserErrorKind::NoLang,
+            }));
+        }
+        let found = match http::block_on(self.translator.detect(word, &langs)) {
+            Ok(found) => found,
+            Err(e) => {
+                warn!("Can't detect language of: {}. {}", word, e);
+                detect::local(word, &langs)
+            }
         };
+        if let Some(d) = found.first() {
+            if d.confidence >= MIN_CONFIDENCE {
+                return Ok(Detected::Sure(d.lang.clone()));
+            }
+        }
+        let mut choice: Vec<Lang> = found.into_iter().map(|d| d.lang).collect();
+        for l in langs {
+            if !choice.contains(&l) {
+                choice.push(l)
+            }
+        }
 
-        stor.upsert(user_id, strategy::AddTranslate { tran })
+        Ok(Detected::Unsure(choice))
     }
 
-    pub fn delete_word(&mut self, user_id: i64, word: &str) -> Result<(), Box<dyn error::Error>> {
-        let mut stor = self.storage.write().unwrap();
-        stor.upsert(
+    // Translates a list of words with one provider request per language.
+    // A word fails if any of its languages fails, the rest are saved
+    pub fn add_words(
+        &self,
+        user_id: i64,
+        words: &[String],
+        lang: &Lang,
+    ) -> Result<Vec<Added>, Box<dyn error::Error>> {
+        let langs: Vec<Lang> = match self.storage.get(user_id)? {
+            Some(user) => user
+                .langs
+                .iter()
+                .filter(|l| l.lang != lang.lang)
+                .cloned()
+                .collect(),
+            None => vec![],
+        };
+        if langs.is_empty() {
+            return Err(Box::new(UserError {
+                kind: UserErrorKind::NoLang,
+            }));
+        }
+        let mut uniq: Vec<String> = vec![];
+        for w in words {
+            if !w.is_empty() && !uniq.iter().any(|u| same_word(u, w)) {
+                uniq.push(w.to_string())
+            }
+        }
+        let batches = http::block_on(join_all(
+            langs
+                .iter()
+                .map(|to| self.translator.senses_batch(&uniq, lang, to)),
+        ));
+
+        let mut added: Vec<Added> = vec![];
+        let mut trans: Vec<storage::Translate> = vec![];
+        for (i, w) in uniq.iter().enumerate() {
+            let mut translates: Vec<Word> = vec![];
+            let mut err: Option<String> = None;
+            for (to, batch) in langs.iter().zip(batches.iter()) {
+                match batch {
+                    Ok(senses) => {
+                        for s in senses.get(i).cloned().unwrap_or_default() {
+                            translates.push(Word {
+                                word: s.word,
+                                lang: to.clone(),
+                                pos: s.pos,
+                                manual: false,
+                            })
+                        }
+                    }
+                    Err(e) => {
+                        err = Some(format!("{}: {}", to, e));
+                        break;
+                    }
+                }
+            }
+            if err.is_none() {
+                trans.push(storage::Translate::new(
+                    Word::new(w, lang.clone()),
+                    translates,
+                ));
+            }
+            added.push(Added {
+                word: w.to_string(),
+                error: err,
+            });
+        }
+        if !trans.is_empty() {
+            self.storage
+                .upsert(user_id, &strategy::AddTranslates { trans })?;
+        }
+
+        Ok(added)
+    }
+
+    // Returns the number of deleted entries, 0 if the word wasn't found
+    pub fn delete_word(
+        &self,
+        user_id: i64,
+        word: &str,
+        lang: Option<&Lang>,
+    ) -> Result<usize, Box<dyn error::Error>> {
+        let strat = strategy::DeleteWord {
+            word: word.to_string(),
+            lang: lang.cloned(),
+            now: unix_now(),
+            ..Default::default()
+        };
+        self.storage.upsert(user_id, &strat)?;
+
+        Ok(strat.deleted.get())
+    }
+
+    // Adds a translation typed by the user, returns the entries of the word
+    pub fn add_translation(
+        &self,
+        user_id: i64,
+        word: &str,
+        tran: &Word,
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        self.storage.upsert(
             user_id,
-            strategy::DeleteWord {
+            &strategy::AddManualTranslate {
                 word: word.to_string(),
+                tran: tran.clone(),
             },
-        )
+        )?;
+        self.word_entries(user_id, word)
+    }
+
+    pub fn delete_translation(
+        &self,
+        user_id: i64,
+        word: &str,
+        tran: &str,
+        lang: Option<&Lang>,
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        self.storage.upsert(
+            user_id,
+            &strategy::DeleteTranslate {
+                word: word.to_string(),
+                tran: tran.to_string(),
+                lang: lang.cloned(),
+            },
+        )?;
+        self.word_entries(user_id, word)
+    }
+
+    pub fn reorder_translations(
+        &self,
+        user_id: i64,
+        word: &str,
+        order: &[String],
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        self.storage.upsert(
+            user_id,
+            &strategy::ReorderTranslates {
+                word: word.to_string(),
+                order: order.to_vec(),
+            },
+        )?;
+        self.word_entries(user_id, word)
+    }
+
+    // Changes the note, examples, grammar or tags of the word, returns the entries of the word
+    pub fn edit_word(
+        &self,
+        user_id: i64,
+        word: &str,
+        edit: &strategy::Edit,
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        self.storage.upsert(
+            user_id,
+            &strategy::EditDetails {
+                word: word.to_string(),
+                edit: edit.clone(),
+            },
+        )?;
+        self.word_entries(user_id, word)
+    }
+
+    // Entries of the word in all languages, empty if there is no such word
+    pub fn word_entries(
+        &self,
+        user_id: i64,
+        word: &str,
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        Ok(self
+            .list_words(user_id, None)?
+            .into_iter()
+            .filter(|t| same_word(&t.word.word, word))
+            .collect())
+    }
+
+    // Languages the word was added in
+    pub fn word_langs(&self, user_id: i64, word: &str) -> Result<Vec<Lang>, Box<dyn error::Error>> {
+        Ok(self
+            .word_entries(user_id, word)?
+            .into_iter()
+            .map(|t| t.word.lang)
+            .collect())
+    }
+
+    // Returns restored words, the last deleted ones if the word is None
+    pub fn restore_word(
+        &self,
+        user_id: i64,
+        word: Option<&str>,
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        let strat = strategy::RestoreWord {
+            word: word.map(|w| w.to_string()),
+            ..Default::default()
+        };
+        self.storage.upsert(user_id, &strat)?;
+
+        Ok(strat.restored.take())
+    }
+
+    // Drops words deleted before the given time from the trash of every user
+    pub fn purge_trash(&self, before: u64) -> Result<usize, Box<dyn error::Error>> {
+        let mut n = 0;
+        for user in self.storage.list()? {
+            if user.trash.iter().all(|d| d.deleted >= before) {
+                continue;
+            }
+            self.storage
+                .upsert(user.id, &strategy::PurgeTrash { before })?;
+            n += 1;
+        }
+
+        Ok(n)
     }
 
     pub fn list_words(
@@ -96,14 +358,13 @@ impl UserWords {
         user_id: i64,
         pattern: Option<&str>,
     ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
-        let stor = self.storage.read().unwrap();
-        match stor.get(user_id) {
+        match self.storage.get(user_id)? {
             Some(u) => match pattern {
                 Some(p) => Ok(u
                     .translates
                     .iter()
-                    .filter(|t| t.contains(&p))
-                    .map(|t| t.clone())
+                    .filter(|t| t.contains(p))
+                    .cloned()
                     .collect()),
                 None => Ok(u.translates.to_vec()),
             },
@@ -111,34 +372,418 @@ impl UserWords {
         }
     }
 
-    pub fn add_lang(&mut self, user_id: i64, lang: &Lang) -> Result<(), Box<dyn error::Error>> {
-        let mut stor = self.storage.write().unwrap();
-        stor.upsert(user_id, strategy::AddLang { lang: lang.clone() })
+    pub fn add_lang(&self, user_id: i64, lang: &Lang) -> Result<(), Box<dyn error::Error>> {
+        self.storage
+            .upsert(user_id, &strategy::AddLang { lang: lang.clone() })
     }
 
-    pub fn delete_lang(&mut self, user_id: i64, lang: &Lang) -> Result<(), Box<dyn error::Error>> {
-        let mut stor = self.storage.write().unwrap();
-        stor.upsert(user_id, strategy::DeleteLang { lang: lang.clone() })
+    pub fn delete_lang(
+        &self,
+        user_id: i64,
+        lang: &Lang,
+        cascade: Option<strategy::Cascade>,
+    ) -> Result<(), Box<dyn error::Error>> {
+        self.storage.upsert(
+            user_id,
+            &strategy::DeleteLang {
+                lang: lang.clone(),
+                cascade,
+                now: unix_now(),
+            },
+        )
+    }
+
+    // Number of words in the language and of translations into it,
+    // what a cascade delete of the language removes
+    pub fn lang_usage(
+        &self,
+        user_id: i64,
+        lang: &Lang,
+    ) -> Result<(usize, usize), Box<dyn error::Error>> {
+        let trs = self.list_words(user_id, None)?;
+        let words = trs.iter().filter(|t| &t.word.lang == lang).count();
+        let translations = trs
+            .iter()
+            .filter(|t| &t.word.lang != lang)
+            .flat_map(|t| t.translates.iter())
+            .filter(|w| &w.lang == lang)
+            .count();
+
+        Ok((words, translations))
     }
 
     pub fn list_langs(&self, user_id: i64) -> Result<Vec<Lang>, Box<dyn error::Error>> {
-        let stor = self.storage.read().unwrap();
-        match stor.get(user_id) {
+        match self.storage.get(user_id)? {
             Some(u) => Ok(u.langs.to_vec()),
             None => Ok(vec![]),
         }
     }
 
+    pub fn refresh_langs(&self) -> Result<(), Box<dyn error::Error>> {
+        let codes = http::block_on(self.translator.supported_langs())?;
+        if !codes.is_empty() {
+            langs::set_available(&codes);
+        }
+
+        Ok(())
+    }
+
     pub fn update_last_seen(
-        &mut self,
+        &self,
         user_id: i64,
         words: Vec<Word>,
     ) -> Result<(), Box<dyn error::Error>> {
-        let mut stor = self.storage.write().unwrap();
-        let last_seen = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(n) => n.as_secs(),
-            Err(_) => panic!("SystemTime before UNIX EPOCH!"),
-        };
-        stor.upsert(user_id, strategy::UpdateLastSeen { words, last_seen })
+        self.storage.upsert(
+            user_id,
+            &strategy::UpdateLastSeen {
+                words,
+                last_seen: unix_now(),
+            },
+        )
+    }
+
+    pub fn due_words(
+        &self,
+        user_id: i64,
+        limit: usize,
+    ) -> Result<Vec<storage::Translate>, Box<dyn error::Error>> {
+        let now = unix_now();
+        match self.storage.get(user_id)? {
+            Some(u) => {
+                let mut trs: Vec<storage::Translate> = u
+                    .translates
+                    .into_iter()
+                    .filter(|t| t.schedule.is_due(now))
+                    .collect();
+                trs.sort_by_key(|t| t.schedule.due);
+                trs.truncate(limit);
+                Ok(trs)
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn review_word(
+        &self,
+        user_id: i64,
+        word: &str,
+        lang: Option<&Lang>,
+        grade: Grade,
+    ) -> Result<(), Box<dyn error::Error>> {
+        self.storage.upsert(
+            user_id,
+            &strategy::Review {
+                word: word.to_string(),
+                lang: lang.cloned(),
+                grade,
+                now: unix_now(),
+            },
+        )
+    }
+}
+
+// Asks the provider for its languages, Lang parsing accepts only them
+pub fn langs_loop(user_words: Arc<UserWords>, interval: Duration) {
+    loop {
+        if let Err(e) = user_words.refresh_langs() {
+            error!("Can't get supported languages: {}", e);
+        }
+        sleep(interval);
+    }
+}
+
+pub fn purge_loop(user_words: Arc<UserWords>, days: u64) {
+    loop {
+        let before = unix_now().saturating_sub(days * DAY);
+        match user_words.purge_trash(before) {
+            Ok(n) if n > 0 => info!("Trash purged for {} users", n),
+            Ok(_) => {}
+            Err(e) => error!("Can't purge trash: {}", e),
+        }
+        sleep(PURGE_INTERVAL);
+    }
+}
+
+fn unix_now() -> u64 {
+    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(n) => n.as_secs(),
+        Err(_) => panic!("SystemTime before UNIX EPOCH!"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error;
+    use std::sync::Arc;
+
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::schedule::Grade;
+    use crate::storage::strategy::{AddTranslate, Cascade, Edit};
+    use crate::storage::{Backend, Translate, Word};
+    use crate::translate::{self, google, Lang};
+    use crate::user::user::{Detected, UserWords};
+
+    use async_trait::async_trait;
+
+    fn translate(word: &str, tr: &str) -> Translate {
+        Translate::new(
+            Word::new(word, "en".parse().unwrap()),
+            vec![Word::new(tr, "ru".parse().unwrap())],
+        )
+    }
+
+    fn user_words(trs: Vec<Translate>) -> UserWords {
+        let stor = MemoryStorage::new();
+        for tran in trs {
+            stor.upsert(1, &AddTranslate { tran }).unwrap();
+        }
+        UserWords::new(Arc::new(stor), Box::new(google::Client::new("")))
+    }
+
+    #[test]
+    fn langs() {
+        let uw = user_words(vec![]);
+        assert!(uw.list_langs(1).unwrap().is_empty());
+        uw.add_lang(1, &"en".parse().unwrap()).unwrap();
+        uw.add_lang(1, &"ru".parse().unwrap()).unwrap();
+        uw.add_lang(1, &"en".parse().unwrap()).unwrap();
+        assert_eq!(
+            vec!["en".parse::<Lang>().unwrap(), "ru".parse().unwrap()],
+            uw.list_langs(1).unwrap()
+        );
+        uw.delete_lang(1, &"en".parse().unwrap(), None).unwrap();
+        assert_eq!(
+            vec!["ru".parse::<Lang>().unwrap()],
+            uw.list_langs(1).unwrap()
+        );
+        assert!(uw.list_langs(2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn add_word_without_langs() {
+        let uw = user_words(vec![]);
+        let word = Word::new("word", "en".parse().unwrap());
+        assert_eq!(
+            "No added langs",
+            uw.add_word(1, &word).unwrap_err().to_string()
+        );
+    }
+
+    // translates "word" to "word lang", fails for kk
+    struct Fake;
+
+    #[async_trait(?Send)]
+    impl translate::Translate for Fake {
+        async fn translate(
+            &self,
+            word: &Word,
+            to: &Lang,
+        ) -> Result<Vec<String>, Box<dyn error::Error>> {
+            if to.lang == "kk" {
+                return Err("no kk".into());
+            }
+            Ok(vec![format!("{} {}", word.word, to)])
+        }
+
+        async fn supported_langs(&self) -> Result<Vec<String>, Box<dyn error::Error>> {
+            Ok(vec![])
+        }
+    }
+
+    #[test]
+    fn add_words() {
+        let uw = UserWords::new(Arc::new(MemoryStorage::new()), Box::new(Fake));
+        let en: Lang = "en".parse().unwrap();
+        uw.add_lang(1, &en).unwrap();
+        uw.add_lang(1, &"ru".parse().unwrap()).unwrap();
+        let words: Vec<String> = ["door", "window", "door", ""]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let added = uw.add_words(1, &words, &en).unwrap();
+        assert_eq!(
+            vec!["door", "window"],
+            added.iter().map(|a| a.word.as_str()).collect::<Vec<&str>>()
+        );
+        assert!(added.iter().all(|a| a.error.is_none()));
+        assert_eq!(
+            "door ru",
+            uw.list_words(1, Some("door")).unwrap()[0].translates[0].word
+        );
+
+        uw.add_lang(1, &"kk".parse().unwrap()).unwrap();
+        let added = uw.add_words(1, &["roof".to_string()], &en).unwrap();
+        assert_eq!(Some("kk: no kk".to_string()), added[0].error);
+        assert!(uw.list_words(1, Some("roof")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn detect_lang() {
+        let uw = UserWords::new(Arc::new(MemoryStorage::new()), Box::new(Fake));
+        assert_eq!(
+            "No added langs",
+            uw.detect_lang(1, "door").unwrap_err().to_string()
+        );
+        let langs: Vec<Lang> = ["en", "ru", "de"]
+            .iter()
+            .map(|l| l.parse().unwrap())
+            .collect();
+        for l in langs.iter() {
+            uw.add_lang(1, l).unwrap();
+        }
+        assert_eq!(
+            Detected::Sure("ru".parse().unwrap()),
+            uw.detect_lang(1, "дверь").unwrap()
+        );
+        assert_eq!(
+            Detected::Unsure(vec![
+                "de".parse().unwrap(),
+                "en".parse().unwrap(),
+                "ru".parse().unwrap()
+            ]),
+            uw.detect_lang(1, "Haus").unwrap()
+        );
+    }
+
+    #[test]
+    fn list_and_delete_words() {
+        let uw = user_words(vec![translate("word", "слово"), translate("door", "дверь")]);
+        assert_eq!(2, uw.list_words(1, None).unwrap().len());
+        assert_eq!(
+            vec![translate("door", "дверь")],
+            uw.list_words(1, Some("две")).unwrap()
+        );
+        assert!(uw.list_words(2, None).unwrap().is_empty());
+
+        assert_eq!(1, uw.delete_word(1, "word", None).unwrap());
+        assert_eq!(
+            vec![translate("door", "дверь")],
+            uw.list_words(1, None).unwrap()
+        );
+        assert_eq!(0, uw.delete_word(1, "word", None).unwrap());
+    }
+
+    #[test]
+    fn edit_translations() {
+        let uw = user_words(vec![translate("word", "слово"), translate("door", "дверь")]);
+        let tran = Word::new("дверца", "ru".parse().unwrap());
+        let trs = uw.add_translation(1, "door", &tran).unwrap();
+        assert_eq!(1, trs.len());
+        assert_eq!(
+            vec!["дверь", "дверца"],
+            trs[0]
+                .translates
+                .iter()
+                .map(|t| t.word.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert!(trs[0].translates[1].manual);
+
+        let trs = uw
+            .reorder_translations(1, "door", &["дверца".to_string()])
+            .unwrap();
+        assert_eq!("дверца", trs[0].translates[0].word);
+        let trs = uw.delete_translation(1, "door", "дверь", None).unwrap();
+        assert_eq!(1, trs[0].translates.len());
+        assert!(uw.add_translation(1, "roof", &tran).unwrap().is_empty());
+        assert_eq!(
+            vec![translate("word", "слово")],
+            uw.word_entries(1, "word").unwrap()
+        );
+    }
+
+    #[test]
+    fn search_details() {
+        let uw = user_words(vec![translate("word", "слово"), translate("door", "дверь")]);
+        let edit = Edit::AddExample("Close the door, please.".to_string());
+        assert_eq!(1, uw.edit_word(1, "door", &edit).unwrap().len());
+        let edit = Edit::AddTags(vec!["house".to_string()]);
+        uw.edit_word(1, "door", &edit).unwrap();
+        assert!(uw.edit_word(1, "roof", &edit).unwrap().is_empty());
+
+        let found = uw.list_words(1, Some("please")).unwrap();
+        assert_eq!(
+            vec!["door"],
+            found
+                .iter()
+                .map(|t| t.word.word.as_str())
+                .collect::<Vec<&str>>()
+        );
+        assert_eq!(1, uw.list_words(1, Some("#house")).unwrap().len());
+        assert!(uw.list_words(1, Some("#hous")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn delete_lang_cascade() {
+        let uw = user_words(vec![translate("word", "слово"), translate("door", "дверь")]);
+        let ru: Lang = "ru".parse().unwrap();
+        let en: Lang = "en".parse().unwrap();
+        assert_eq!((0, 2), uw.lang_usage(1, &ru).unwrap());
+        assert_eq!((2, 0), uw.lang_usage(1, &en).unwrap());
+
+        uw.delete_lang(1, &ru, Some(Cascade::Delete)).unwrap();
+        assert_eq!((0, 0), uw.lang_usage(1, &ru).unwrap());
+        assert!(uw
+            .list_words(1, None)
+            .unwrap()
+            .iter()
+            .all(|t| t.translates.is_empty()));
+        uw.delete_lang(1, &en, Some(Cascade::Archive)).unwrap();
+        assert!(uw.list_words(1, None).unwrap().is_empty());
+        assert_eq!(2, uw.restore_word(1, None).unwrap().len());
+        assert_eq!((2, 0), uw.lang_usage(1, &en).unwrap());
+    }
+
+    #[test]
+    fn delete_word_lang() {
+        let mut die = translate("die", "умереть");
+        let uw = user_words(vec![die.clone()]);
+        die.word.lang = "de".parse().unwrap();
+        uw.storage
+            .upsert(1, &AddTranslate { tran: die.clone() })
+            .unwrap();
+        assert_eq!(
+            vec!["en".parse::<Lang>().unwrap(), "de".parse().unwrap()],
+            uw.word_langs(1, "die").unwrap()
+        );
+
+        uw.delete_word(1, "die", Some(&"en".parse().unwrap()))
+            .unwrap();
+        assert_eq!(vec![die], uw.list_words(1, None).unwrap());
+    }
+
+    #[test]
+    fn restore_words() {
+        let uw = user_words(vec![translate("word", "слово"), translate("door", "дверь")]);
+        assert!(uw.restore_word(1, None).unwrap().is_empty());
+        uw.review_word(1, "word", None, Grade::Good).unwrap();
+        let reviewed = uw.list_words(1, Some("word")).unwrap();
+        uw.delete_word(1, "word", None).unwrap();
+        uw.delete_word(1, "door", None).unwrap();
+        assert!(uw.list_words(1, None).unwrap().is_empty());
+
+        assert_eq!(
+            vec![translate("door", "дверь")],
+            uw.restore_word(1, None).unwrap()
+        );
+        assert_eq!(reviewed, uw.restore_word(1, Some("word")).unwrap());
+        assert_eq!(2, uw.list_words(1, None).unwrap().len());
+        assert_eq!(reviewed, uw.list_words(1, Some("word")).unwrap());
+
+        uw.delete_word(1, "word", None).unwrap();
+        assert_eq!(0, uw.purge_trash(0).unwrap());
+        assert_eq!(1, uw.purge_trash(u64::MAX).unwrap());
+        assert!(uw.restore_word(1, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn review_words() {
+        let uw = user_words(vec![translate("word", "слово"), translate("door", "дверь")]);
+        assert_eq!(1, uw.due_words(1, 1).unwrap().len());
+        assert_eq!(2, uw.due_words(1, 10).unwrap().len());
+
+        uw.review_word(1, "word", None, Grade::Good).unwrap();
+        let due = uw.due_words(1, 10).unwrap();
+        assert_eq!(vec![translate("door", "дверь")], due);
     }
 }